@@ -86,6 +86,16 @@ fn inputs(c: &mut Criterion) {
         b.iter(|| length(&db, input));
     });
 
+    group.bench_function(BenchmarkId::new("new_batch", "Input"), |b| {
+        b.iter(|| {
+            let inputs = Input::new_batch(
+                &db,
+                (0..1000).map(|_| Input::builder("hello, world!".to_owned())),
+            );
+            length(&db, inputs[0]);
+        })
+    });
+
     group.finish();
 }
 
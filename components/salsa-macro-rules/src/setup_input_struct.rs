@@ -55,6 +55,7 @@ macro_rules! setup_input_struct {
             $zalsa_struct:ident,
             $Configuration:ident,
             $Builder:ident,
+            $Updater:ident,
             $CACHE:ident,
             $Db:ident,
         ]
@@ -141,6 +142,26 @@ macro_rules! setup_input_struct {
                     builder::new_builder($($zalsa::maybe_default!($field_option, $field_ty, $field_id,)),*)
                 }
 
+                $zalsa::macro_if! { if $is_singleton {} else {
+                    /// Creates a new input for each of the given builders.
+                    ///
+                    /// This is faster than calling `new` on each builder when creating many inputs at once.
+                    pub fn new_batch<$Db>(db: &$Db, builders: impl IntoIterator<Item = <Self as $zalsa_struct::HasBuilder>::Builder>) -> Vec<Self>
+                    where
+                        // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                        $Db: ?Sized + salsa::Database,
+                    {
+                        let current_revision = $zalsa::current_revision(db);
+                        let ingredient = $Configuration::ingredient(db.as_dyn_database());
+                        ingredient.new_inputs(
+                            db.as_dyn_database(),
+                            builders
+                                .into_iter()
+                                .map(|builder| builder::builder_into_inner(builder, current_revision)),
+                        )
+                    }
+                }}
+
                 $(
                     $field_getter_vis fn $field_getter_id<'db, $Db>(self, db: &'db $Db) -> $zalsa::maybe_cloned_ty!($field_option, 'db, $field_ty)
                     where
@@ -167,17 +188,44 @@ macro_rules! setup_input_struct {
                         // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
                         $Db: ?Sized + $zalsa::Database,
                     {
-                        let (ingredient, revision) = $Configuration::ingredient_mut(db.as_dyn_database_mut());
                         $zalsa::input::SetterImpl::new(
-                            revision,
+                            db.as_dyn_database_mut(),
                             self,
                             $field_index,
-                            ingredient,
+                            $Configuration::ingredient_mut,
+                            |fields| &fields.$field_index,
                             |fields, f| std::mem::replace(&mut fields.$field_index, f),
                         )
                     }
                 )*
 
+                /// Starts a batch of field changes that are applied together by
+                /// [`apply`](`builder::$Updater::apply`), triggering only a single new revision.
+                #[must_use]
+                pub fn update<'db, $Db>(self, db: &'db mut $Db) -> builder::$Updater<'db>
+                where
+                    // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                    $Db: ?Sized + $zalsa::Database,
+                {
+                    builder::new_updater($zalsa::AsId::as_id(&self), db.as_dyn_database_mut())
+                }
+
+                /// Deletes this input, dropping its field values and all memoized values of tracked functions on it.
+                /// Queries that read from this input will be re-executed; its id may be re-used by a new input
+                /// created in a later revision.
+                ///
+                /// Like a setter, this starts a new revision (cancelling other database handles).
+                pub fn delete<$Db>(self, db: &mut $Db)
+                where
+                    // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                    $Db: ?Sized + $zalsa::Database,
+                {
+                    let (ingredient, runtime) = $Configuration::ingredient_mut(db.as_dyn_database_mut());
+                    ingredient.delete_input(runtime, self);
+                    let db = db.as_dyn_database();
+                    $Configuration::ingredient(db).discard_deleted_input(db, self);
+                }
+
                 $zalsa::macro_if! { $is_singleton =>
                     pub fn try_get<$Db>(db: &$Db) -> Option<Self>
                     where
@@ -236,6 +284,61 @@ macro_rules! setup_input_struct {
                 }
             }
 
+            // Implement the setters and `apply` here instead of inside the builder module
+            // because $Configuration can't be named in `builder` and because the setters
+            // must have the same visibility as the fields.
+            impl builder::$Updater<'_> {
+                $(
+                    /// Sets the new value of the field `$field_id`.
+                    #[must_use]
+                    $field_setter_vis fn $field_id(mut self, value: $field_ty) -> Self {
+                        builder::updater_fields_mut(&mut self).$field_index = Some(value);
+                        self
+                    }
+
+                    /// Sets the durability for the new value of the field `$field_id`.
+                    /// If omitted, uses the durability of the previous value.
+                    ///
+                    /// Has no effect unless a new value for `$field_id` is set as well.
+                    #[must_use]
+                    $field_setter_vis fn $field_durability_id(mut self, durability: salsa::Durability) -> Self {
+                        builder::updater_durabilities_mut(&mut self)[$field_index] = Some(durability);
+                        self
+                    }
+                )*
+
+                /// Applies all field changes at once.
+                ///
+                /// Like a setter, this starts a new revision (cancelling other database handles),
+                /// but only one revision is started no matter how many fields were changed.
+                /// If no field was changed, no new revision is started.
+                pub fn apply(self) {
+                    let (db, id, fields, durabilities) = builder::updater_into_inner(self);
+                    if true $(&& fields.$field_index.is_none())* {
+                        return;
+                    }
+
+                    let this: $Struct = $zalsa::FromId::from_id(id);
+                    let (ingredient, runtime) = $Configuration::ingredient_mut(db);
+                    let ingredient_index = $zalsa::Ingredient::ingredient_index(ingredient);
+                    let mut set = [false; $N];
+                    $(
+                        if let Some(value) = fields.$field_index {
+                            ingredient.set_field(runtime, this, $field_index, durabilities[$field_index], |fields| {
+                                fields.$field_index = value;
+                            });
+                            set[$field_index] = true;
+                        }
+                    )*
+
+                    for (field_index, set) in set.into_iter().enumerate() {
+                        if set {
+                            $zalsa_struct::IngredientImpl::<$Configuration>::report_set_field(db, ingredient_index, this, field_index);
+                        }
+                    }
+                }
+            }
+
             mod builder {
                 use super::*;
 
@@ -259,6 +362,28 @@ macro_rules! setup_input_struct {
                     (builder.fields, stamps)
                 }
 
+                pub(super) fn new_updater(id: salsa::Id, db: &mut dyn $zalsa::Database) -> $Updater<'_> {
+                    $Updater {
+                        db,
+                        id,
+                        fields: ($(None::<$field_ty>,)*),
+                        durabilities: [None; $N],
+                    }
+                }
+
+                pub(super) fn updater_fields_mut<'u>(updater: &'u mut $Updater<'_>) -> &'u mut ($(Option<$field_ty>,)*) {
+                    &mut updater.fields
+                }
+
+                pub(super) fn updater_durabilities_mut<'u>(updater: &'u mut $Updater<'_>) -> &'u mut [Option<salsa::Durability>; $N] {
+                    &mut updater.durabilities
+                }
+
+                #[allow(clippy::type_complexity)]
+                pub(super) fn updater_into_inner(updater: $Updater<'_>) -> (&mut dyn $zalsa::Database, salsa::Id, ($(Option<$field_ty>,)*), [Option<salsa::Durability>; $N]) {
+                    (updater.db, updater.id, updater.fields, updater.durabilities)
+                }
+
                 #[must_use]
                 pub struct $Builder {
                     /// The field values.
@@ -268,6 +393,22 @@ macro_rules! setup_input_struct {
                     durabilities: [salsa::Durability; $N],
                 }
 
+                /// Accumulates changes to the fields of an input; created by `update`.
+                #[must_use]
+                pub struct $Updater<'db> {
+                    /// The database to apply the changes to.
+                    db: &'db mut dyn $zalsa::Database,
+
+                    /// The id of the input being updated.
+                    id: salsa::Id,
+
+                    /// The new field values, if set.
+                    fields: ($(Option<$field_ty>,)*),
+
+                    /// The new durabilities per field, if set.
+                    durabilities: [Option<salsa::Durability>; $N],
+                }
+
                 impl $Builder {
                     /// Sets the durability of all fields.
                     ///
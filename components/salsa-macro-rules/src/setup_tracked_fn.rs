@@ -55,6 +55,9 @@ macro_rules! setup_tracked_fn {
         // True if we `return_ref` flag was given to the function
         return_ref: $return_ref:tt,
 
+        // Durability the memoized value is expected to have (an `Option<Durability>`)
+        expect_durability: $expect_durability:expr,
+
         // Annoyingly macro-rules hygiene does not extend to items defined in the macro.
         // We have the procedural macro generate names for those items that are
         // not used elsewhere in the user's code.
@@ -160,6 +163,12 @@ macro_rules! setup_tracked_fn {
 
                 const CYCLE_STRATEGY: $zalsa::CycleRecoveryStrategy = $zalsa::CycleRecoveryStrategy::$cycle_recovery_strategy;
 
+                const EXPECTED_DURABILITY: Option<salsa::Durability> = if cfg!(debug_assertions) {
+                    $expect_durability
+                } else {
+                    None
+                };
+
                 fn should_backdate_value(
                     old_value: &Self::Output<'_>,
                     new_value: &Self::Output<'_>,
@@ -181,7 +190,7 @@ macro_rules! setup_tracked_fn {
 
                 fn recover_from_cycle<$db_lt>(
                     db: &$db_lt dyn $Db,
-                    cycle: &$zalsa::Cycle,
+                    cycle: &$zalsa::CycleContext,
                     ($($input_id),*): ($($input_ty),*)
                 ) -> Self::Output<$db_lt> {
                     $($cycle_recovery_fn)*(db, cycle, $($input_id),*)
@@ -244,6 +253,25 @@ macro_rules! setup_tracked_fn {
                     $Configuration::fn_ingredient($db).accumulated_by::<A>($db, key)
                 }
 
+                /// Returns the durability of the memoized value for these arguments, and (in debug
+                /// builds) the input that lowered it, or `None` if there is no memoized value.
+                /// Does not execute the function nor record any dependency.
+                pub fn durability_info<$db_lt>(
+                    $db: &$db_lt dyn $Db,
+                    $($input_id: $input_ty,)*
+                ) -> Option<salsa::DurabilityInfo> {
+                    use salsa::plumbing as $zalsa;
+                    let key = $zalsa::macro_if! {
+                        if $needs_interner {
+                            $Configuration::intern_ingredient($db).intern_id($db.as_dyn_database(), ($($input_id),*))
+                        } else {
+                            $zalsa::AsId::as_id(&($($input_id),*))
+                        }
+                    };
+
+                    $Configuration::fn_ingredient($db).durability_info($db, key)
+                }
+
                 $zalsa::macro_if! { $is_specifiable =>
                     pub fn specify<$db_lt>(
                         $db: &$db_lt dyn $Db,
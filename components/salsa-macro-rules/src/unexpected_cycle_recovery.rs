@@ -7,7 +7,21 @@ macro_rules! unexpected_cycle_recovery {
         {
             std::mem::drop($db);
             std::mem::drop(($($other_inputs),*));
-            panic!("cannot recover from cycle `{:?}`", $cycle)
+            panic!("cannot recover from cycle `{:?}`", $cycle.cycle())
+        }
+    }
+}
+
+// Macro that generates the body of the cycle recovery function
+// for tracked functions with the `cycle_result` option: a
+// convenience recovery function that returns the cycle as an error.
+#[macro_export]
+macro_rules! cycle_result_recovery {
+    ($db:ident, $cycle:ident, $($other_inputs:ident),*) => {
+        {
+            std::mem::drop($db);
+            std::mem::drop(($($other_inputs),*));
+            Err($cycle.cycle().clone())
         }
     }
 }
@@ -39,7 +39,9 @@ impl AllowedOptions for Accumulator {
     const DATA: bool = false;
     const DB: bool = false;
     const RECOVERY_FN: bool = false;
+    const CYCLE_RESULT: bool = false;
     const LRU: bool = false;
+    const EXPECT_DURABILITY: bool = false;
     const CONSTRUCTOR_NAME: bool = false;
 }
 
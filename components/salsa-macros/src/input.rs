@@ -51,8 +51,12 @@ impl crate::options::AllowedOptions for InputStruct {
 
     const RECOVERY_FN: bool = false;
 
+    const CYCLE_RESULT: bool = false;
+
     const LRU: bool = false;
 
+    const EXPECT_DURABILITY: bool = false;
+
     const CONSTRUCTOR_NAME: bool = true;
 }
 
@@ -64,6 +68,8 @@ impl SalsaStructAllowedOptions for InputStruct {
     const HAS_LIFETIME: bool = false;
 
     const ALLOW_DEFAULT: bool = true;
+
+    const BANNED_FIELD_NAMES: &'static [&'static str] = &["update", "apply", "delete"];
 }
 
 struct Macro {
@@ -98,6 +104,7 @@ impl Macro {
         let zalsa_struct = self.hygiene.ident("zalsa_struct");
         let Configuration = self.hygiene.ident("Configuration");
         let Builder = self.hygiene.ident("Builder");
+        let Updater = self.hygiene.ident("Updater");
         let CACHE = self.hygiene.ident("CACHE");
         let Db = self.hygiene.ident("Db");
 
@@ -125,6 +132,7 @@ impl Macro {
                         #zalsa_struct,
                         #Configuration,
                         #Builder,
+                        #Updater,
                         #CACHE,
                         #Db,
                     ]
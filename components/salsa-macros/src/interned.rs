@@ -52,8 +52,12 @@ impl crate::options::AllowedOptions for InternedStruct {
 
     const RECOVERY_FN: bool = false;
 
+    const CYCLE_RESULT: bool = false;
+
     const LRU: bool = false;
 
+    const EXPECT_DURABILITY: bool = false;
+
     const CONSTRUCTOR_NAME: bool = true;
 }
 
@@ -65,6 +69,8 @@ impl SalsaStructAllowedOptions for InternedStruct {
     const HAS_LIFETIME: bool = true;
 
     const ALLOW_DEFAULT: bool = false;
+
+    const BANNED_FIELD_NAMES: &'static [&'static str] = &[];
 }
 
 struct Macro {
@@ -49,6 +49,13 @@ pub(crate) struct Options<A: AllowedOptions> {
     /// If this is `Some`, the value is the `<path>`.
     pub recovery_fn: Option<syn::Path>,
 
+    /// The `cycle_result` option is used to signal that a tracked function returns
+    /// `Result<T, salsa::Cycle>`: it is a shorthand for a recovery function
+    /// that returns `Err(cycle)`.
+    ///
+    /// If this is `Some`, the value is the `cycle_result` identifier.
+    pub cycle_result: Option<syn::Ident>,
+
     /// The `data = <ident>` option is used to define the name of the data type for an interned
     /// struct.
     ///
@@ -60,6 +67,12 @@ pub(crate) struct Options<A: AllowedOptions> {
     /// If this is `Some`, the value is the `<usize>`.
     pub lru: Option<usize>,
 
+    /// The `expect_durability = <expr>` option asserts (in debug builds) that the
+    /// memoized value of a tracked function has at least the given durability.
+    ///
+    /// If this is `Some`, the value is the `<expr>`.
+    pub expect_durability: Option<syn::Expr>,
+
     /// The `constructor = <ident>` option lets the user specify the name of
     /// the constructor of a salsa struct.
     ///
@@ -80,10 +93,12 @@ impl<A: AllowedOptions> Default for Options<A> {
             no_clone: Default::default(),
             db_path: Default::default(),
             recovery_fn: Default::default(),
+            cycle_result: Default::default(),
             data: Default::default(),
             constructor_name: Default::default(),
             phantom: Default::default(),
             lru: Default::default(),
+            expect_durability: Default::default(),
             singleton: Default::default(),
         }
     }
@@ -100,7 +115,9 @@ pub(crate) trait AllowedOptions {
     const DATA: bool;
     const DB: bool;
     const RECOVERY_FN: bool;
+    const CYCLE_RESULT: bool;
     const LRU: bool;
+    const EXPECT_DURABILITY: bool;
     const CONSTRUCTOR_NAME: bool;
 }
 
@@ -223,6 +240,20 @@ impl<A: AllowedOptions> syn::parse::Parse for Options<A> {
                         "`recovery_fn` option not allowed here",
                     ));
                 }
+            } else if ident == "cycle_result" {
+                if A::CYCLE_RESULT {
+                    if let Some(old) = options.cycle_result.replace(ident) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `cycle_result` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`cycle_result` option not allowed here",
+                    ));
+                }
             } else if ident == "data" {
                 if A::DATA {
                     let _eq = Equals::parse(input)?;
@@ -250,6 +281,22 @@ impl<A: AllowedOptions> syn::parse::Parse for Options<A> {
                         "`lru` option not allowed here",
                     ));
                 }
+            } else if ident == "expect_durability" {
+                if A::EXPECT_DURABILITY {
+                    let _eq = Equals::parse(input)?;
+                    let expr = syn::Expr::parse(input)?;
+                    if let Some(old) = options.expect_durability.replace(expr) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `expect_durability` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`expect_durability` option not allowed here",
+                    ));
+                }
             } else if ident == "constructor" {
                 if A::CONSTRUCTOR_NAME {
                     let _eq = Equals::parse(input)?;
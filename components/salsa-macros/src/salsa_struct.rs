@@ -50,6 +50,10 @@ pub(crate) trait SalsaStructAllowedOptions: AllowedOptions {
 
     /// Are `#[default]` fields allowed?
     const ALLOW_DEFAULT: bool;
+
+    /// Field names that clash with the methods generated for this kind of struct,
+    /// in addition to the ones disallowed in all salsa structs.
+    const BANNED_FIELD_NAMES: &'static [&'static str];
 }
 
 pub(crate) struct SalsaField<'s> {
@@ -105,6 +109,7 @@ where
 
         this.maybe_disallow_id_fields()?;
         this.maybe_disallow_default_fields()?;
+        this.disallow_banned_field_names()?;
 
         this.check_generics()?;
 
@@ -169,6 +174,28 @@ where
         Ok(())
     }
 
+    /// Disallow fields whose name (or getter name) clashes with a method generated
+    /// for this kind of struct, see [`SalsaStructAllowedOptions::BANNED_FIELD_NAMES`].
+    fn disallow_banned_field_names(&self) -> syn::Result<()> {
+        for ef in &self.fields {
+            let field_name = ef.field.ident.as_ref().unwrap();
+            for name in [field_name, &ef.get_name] {
+                if A::BANNED_FIELD_NAMES.iter().any(|n| name == n) {
+                    return Err(syn::Error::new(
+                        name.span(),
+                        format!(
+                            "the field name `{name}` is disallowed in `#[salsa::{}]` structs, \
+                             as it clashes with the generated `{name}` method",
+                            A::KIND
+                        ),
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     /// Check that the generic parameters look as expected for this kind of struct.
     fn check_generics(&self) -> syn::Result<()> {
         if A::HAS_LIFETIME {
@@ -41,8 +41,12 @@ impl crate::options::AllowedOptions for TrackedFn {
 
     const RECOVERY_FN: bool = true;
 
+    const CYCLE_RESULT: bool = true;
+
     const LRU: bool = true;
 
+    const EXPECT_DURABILITY: bool = true;
+
     const CONSTRUCTOR_NAME: bool = false;
 }
 
@@ -97,6 +101,13 @@ impl Macro {
             }
         }
 
+        if let (Some(_), Some(token)) = (&self.args.recovery_fn, &self.args.cycle_result) {
+            return Err(syn::Error::new_spanned(
+                token,
+                "the `recovery_fn` and `cycle_result` options cannot be used together",
+            ));
+        }
+
         if let (Some(_), Some(token)) = (&self.args.lru, &self.args.specify) {
             return Err(syn::Error::new_spanned(
                 token,
@@ -113,6 +124,8 @@ impl Macro {
 
         let return_ref: bool = self.args.return_ref.is_some();
 
+        let expect_durability = self.expect_durability();
+
         Ok(crate::debug::dump_tokens(
             fn_name,
             quote![salsa::plumbing::setup_tracked_fn! {
@@ -133,6 +146,7 @@ impl Macro {
                 needs_interner: #needs_interner,
                 lru: #lru,
                 return_ref: #return_ref,
+                expect_durability: #expect_durability,
                 unused_names: [
                     #zalsa,
                     #Configuration,
@@ -160,9 +174,31 @@ impl Macro {
 
         Ok(ValidFn { db_ident, db_path })
     }
+
+    /// The durability given with `expect_durability`, as an `Option<salsa::Durability>`.
+    /// The names of the predefined levels (`LOW`, `MEDIUM`, `HIGH`) can be used unqualified.
+    fn expect_durability(&self) -> TokenStream {
+        match &self.args.expect_durability {
+            Some(syn::Expr::Path(path))
+                if ["LOW", "MEDIUM", "HIGH"]
+                    .iter()
+                    .any(|name| path.path.is_ident(name)) =>
+            {
+                quote!(Some(salsa::Durability::#path))
+            }
+            Some(expr) => quote!(Some(#expr)),
+            None => quote!(None),
+        }
+    }
+
     fn cycle_recovery(&self) -> (TokenStream, TokenStream) {
         if let Some(recovery_fn) = &self.args.recovery_fn {
             (quote!((#recovery_fn)), quote!(Fallback))
+        } else if self.args.cycle_result.is_some() {
+            (
+                quote!((salsa::plumbing::cycle_result_recovery!)),
+                quote!(Fallback),
+            )
         } else {
             (
                 quote!((salsa::plumbing::unexpected_cycle_recovery!)),
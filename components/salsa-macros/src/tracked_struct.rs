@@ -47,8 +47,12 @@ impl crate::options::AllowedOptions for TrackedStruct {
 
     const RECOVERY_FN: bool = false;
 
+    const CYCLE_RESULT: bool = false;
+
     const LRU: bool = false;
 
+    const EXPECT_DURABILITY: bool = false;
+
     const CONSTRUCTOR_NAME: bool = true;
 }
 
@@ -60,6 +64,8 @@ impl SalsaStructAllowedOptions for TrackedStruct {
     const HAS_LIFETIME: bool = true;
 
     const ALLOW_DEFAULT: bool = false;
+
+    const BANNED_FIELD_NAMES: &'static [&'static str] = &[];
 }
 
 struct Macro {
@@ -0,0 +1,87 @@
+//! Analyzes a log written by `salsa::EventRecorder`.
+//!
+//! ```text
+//! event-log <log> executed <revision>   # queries executed in a revision, e.g. `R7` or `7`
+//! event-log <log> blocked               # which thread blocked on whom, and for which query
+//! event-log <log> summary               # number of events of each kind, per revision
+//! ```
+use std::{collections::BTreeMap, fs::File, io::BufReader};
+
+use eyre::{bail, eyre, Context, Result};
+use salsa::{RecordedEvent, RecordedEventKind};
+
+fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (path, command) = match &args[..] {
+        [path, command, ..] => (path, command.as_str()),
+        _ => bail!("Usage: ./event-log <log> (executed <revision> | blocked | summary)"),
+    };
+
+    let file = File::open(path).wrap_err_with(|| format!("failed to open {path}"))?;
+    let events = RecordedEvent::read_log(BufReader::new(file))
+        .wrap_err_with(|| format!("failed to read {path}"))?;
+
+    match (command, &args[2..]) {
+        ("executed", [revision]) => {
+            let revision = parse_revision(revision)?;
+            for key in executed_in(&events, revision) {
+                println!("{key}");
+            }
+        }
+        ("blocked", []) => {
+            for event in events
+                .iter()
+                .filter(|e| e.kind == RecordedEventKind::WillBlockOn)
+            {
+                println!(
+                    "R{}: {} blocked on {} waiting for {}",
+                    event.revision,
+                    event.thread,
+                    event.other_thread.as_deref().unwrap_or("?"),
+                    display_key(event),
+                );
+            }
+        }
+        ("summary", []) => {
+            let mut counts: BTreeMap<u64, BTreeMap<RecordedEventKind, usize>> = BTreeMap::new();
+            for event in &events {
+                *counts
+                    .entry(event.revision)
+                    .or_default()
+                    .entry(event.kind)
+                    .or_default() += 1;
+            }
+            for (revision, kinds) in counts {
+                println!("R{revision}:");
+                for (kind, count) in kinds {
+                    println!("    {kind:?}: {count}");
+                }
+            }
+        }
+        _ => bail!("unknown command `{}`", args[1..].join(" ")),
+    }
+
+    Ok(())
+}
+
+/// The queries that were executed in `revision`, in execution order.
+fn executed_in(events: &[RecordedEvent], revision: u64) -> impl Iterator<Item = String> + '_ {
+    events
+        .iter()
+        .filter(move |e| e.revision == revision && e.kind == RecordedEventKind::WillExecute)
+        .map(display_key)
+}
+
+fn display_key(event: &RecordedEvent) -> String {
+    match &event.key {
+        Some(key) => key.to_string(),
+        None => "?".to_string(),
+    }
+}
+
+fn parse_revision(s: &str) -> Result<u64> {
+    s.strip_prefix('R')
+        .unwrap_or(s)
+        .parse()
+        .map_err(|_| eyre!("invalid revision `{s}`"))
+}
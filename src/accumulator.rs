@@ -16,7 +16,7 @@ use crate::{
     plumbing::JarAux,
     zalsa::IngredientIndex,
     zalsa_local::QueryOrigin,
-    Database, DatabaseKeyIndex, Id, Revision,
+    Database, DatabaseKeyIndex, Id, Revision, Runtime,
 };
 
 mod accumulated;
@@ -134,7 +134,7 @@ impl<A: Accumulator> Ingredient for IngredientImpl<A> {
         false
     }
 
-    fn reset_for_new_revision(&mut self) {
+    fn reset_for_new_revision(&mut self, _runtime: &mut Runtime) {
         panic!("unexpected reset on accumulator")
     }
 
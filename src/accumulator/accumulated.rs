@@ -12,6 +12,9 @@ pub(crate) trait AnyAccumulated: Any + Debug + Send + Sync {
     fn as_dyn_any(&self) -> &dyn Any;
     fn as_dyn_any_mut(&mut self) -> &mut dyn Any;
     fn cloned(&self) -> Box<dyn AnyAccumulated>;
+
+    /// Moves the values accumulated in `other`, which must be of the same type, to the end.
+    fn append(&mut self, other: &mut dyn AnyAccumulated);
 }
 
 impl<A: Accumulator> Accumulated<A> {
@@ -48,6 +51,11 @@ where
         let this: Self = self.clone();
         Box::new(this)
     }
+
+    fn append(&mut self, other: &mut dyn AnyAccumulated) {
+        let other = other.as_dyn_any_mut().downcast_mut::<Self>().unwrap();
+        self.values.append(&mut other.values);
+    }
 }
 
 impl dyn AnyAccumulated {
@@ -1,3 +1,5 @@
+use std::collections::hash_map::Entry;
+
 use rustc_hash::FxHashMap;
 
 use crate::IngredientIndex;
@@ -17,6 +19,18 @@ impl AccumulatedMap {
             .accumulate(value);
     }
 
+    /// Moves the values accumulated in `other` after the values of `self`.
+    pub fn append(&mut self, other: AccumulatedMap) {
+        for (index, mut values) in other.map {
+            match self.map.entry(index) {
+                Entry::Occupied(mut entry) => entry.get_mut().append(&mut *values),
+                Entry::Vacant(entry) => {
+                    entry.insert(values);
+                }
+            }
+        }
+    }
+
     pub fn extend_with_accumulated<A: Accumulator>(
         &self,
         index: IngredientIndex,
@@ -1,12 +1,16 @@
+use std::sync::Arc;
+
+use parking_lot::Mutex;
 use rustc_hash::FxHashMap;
 
 use super::zalsa_local::{EdgeKind, QueryEdges, QueryOrigin, QueryRevisions};
 use crate::tracked_struct::IdentityHash;
 use crate::{
     accumulator::accumulated_map::AccumulatedMap,
-    durability::Durability,
+    durability::{Durability, LoweredBy},
     hash::FxIndexSet,
     key::{DatabaseKeyIndex, DependencyIndex},
+    parallel::TaskKey,
     tracked_struct::{Disambiguator, Identity},
     zalsa_local::EMPTY_DEPENDENCIES,
     Cycle, Id, Revision,
@@ -20,6 +24,10 @@ pub(crate) struct ActiveQuery {
     /// Minimum durability of inputs observed so far.
     pub(crate) durability: Durability,
 
+    /// The first input read with durability `durability`, if any.
+    /// `None` if there were no reads or if the durability was lowered by an untracked read.
+    durability_lowered_by: LoweredBy,
+
     /// Maximum revision of all inputs observed. If we observe an
     /// untracked read, this will be set to the most recent revision.
     pub(crate) changed_at: Revision,
@@ -54,13 +62,38 @@ pub(crate) struct ActiveQuery {
     /// Stores the values accumulated to the given ingredient.
     /// The type of accumulated value is erased but known to the ingredient.
     pub(crate) accumulated: AccumulatedMap,
+
+    /// Set once the query runs tasks in parallel (see [`crate::parallel`]), on its own frame and
+    /// on the frames of the tasks: `disambiguator_map` and `tracked_struct_ids` are moved here,
+    /// so that tracked structs created by different tasks are disambiguated from each other.
+    shared_identities: Option<Arc<Mutex<SharedIdentities>>>,
+
+    /// The parallel task this frame runs, or the default key for the frame of a query.
+    /// Part of the identity of the tracked structs it creates.
+    task: TaskKey,
+
+    /// The number of parallel operations this frame has started, see [`TaskKey`].
+    operations: usize,
+}
+
+/// The tracked struct identities of a query that runs tasks in parallel, see [`ActiveQuery`].
+///
+/// The identities include the [`TaskKey`] of the creating task, so each task counts
+/// the disambiguators of its tracked structs on its own, whatever the order the tasks run in.
+#[derive(Debug, Default)]
+pub(crate) struct SharedIdentities {
+    disambiguator_map: FxHashMap<IdentityHash, Disambiguator>,
+    tracked_struct_ids: FxHashMap<Identity, Id>,
 }
 
 impl ActiveQuery {
-    pub(super) fn new(database_key_index: DatabaseKeyIndex) -> Self {
+    /// Creates the frame of a query that has not read anything yet:
+    /// `max_durability` is the highest durability level of the database.
+    pub(super) fn new(database_key_index: DatabaseKeyIndex, max_durability: Durability) -> Self {
         ActiveQuery {
             database_key_index,
-            durability: Durability::MAX,
+            durability: max_durability,
+            durability_lowered_by: LoweredBy::default(),
             changed_at: Revision::start(),
             input_outputs: FxIndexSet::default(),
             untracked_read: false,
@@ -68,6 +101,9 @@ impl ActiveQuery {
             disambiguator_map: Default::default(),
             tracked_struct_ids: Default::default(),
             accumulated: Default::default(),
+            shared_identities: None,
+            task: TaskKey::default(),
+            operations: 0,
         }
     }
 
@@ -78,19 +114,36 @@ impl ActiveQuery {
         revision: Revision,
     ) {
         self.input_outputs.insert((EdgeKind::Input, input));
-        self.durability = self.durability.min(durability);
+        self.lower_durability(durability, LoweredBy::new(Some(input)));
         self.changed_at = self.changed_at.max(revision);
     }
 
+    /// Lowers the durability to `durability`, if that is lower, because of `lowered_by`.
+    /// The first read at the durability the frame starts out with (if not preceded by an
+    /// untracked read) is recorded as well, as it is what keeps the durability at that level.
+    fn lower_durability(&mut self, durability: Durability, lowered_by: LoweredBy) {
+        let first_at_level = durability == self.durability
+            && self.durability_lowered_by.get().is_none()
+            && !self.untracked_read;
+        if durability < self.durability || first_at_level {
+            self.durability = durability;
+            self.durability_lowered_by = lowered_by;
+        }
+    }
+
     pub(super) fn add_untracked_read(&mut self, changed_at: Revision) {
         self.untracked_read = true;
         self.durability = Durability::LOW;
+        self.durability_lowered_by = LoweredBy::default();
         self.changed_at = changed_at;
     }
 
     pub(super) fn add_synthetic_read(&mut self, durability: Durability, revision: Revision) {
         self.untracked_read = true;
-        self.durability = self.durability.min(durability);
+        if durability < self.durability {
+            self.durability = durability;
+            self.durability_lowered_by = LoweredBy::default();
+        }
         self.changed_at = self.changed_at.max(revision);
     }
 
@@ -119,11 +172,17 @@ impl ActiveQuery {
             QueryOrigin::Derived(edges)
         };
 
+        let tracked_struct_ids = match self.shared_identities {
+            Some(shared) => std::mem::take(&mut shared.lock().tracked_struct_ids),
+            None => self.tracked_struct_ids,
+        };
+
         QueryRevisions {
             changed_at: self.changed_at,
             origin,
             durability: self.durability,
-            tracked_struct_ids: self.tracked_struct_ids,
+            durability_lowered_by: self.durability_lowered_by,
+            tracked_struct_ids,
             accumulated: self.accumulated,
         }
     }
@@ -132,10 +191,15 @@ impl ActiveQuery {
     /// Used during cycle recovery, see [`Runtime::unblock_cycle_and_maybe_throw`].
     pub(super) fn add_from(&mut self, other: &ActiveQuery) {
         self.changed_at = self.changed_at.max(other.changed_at);
-        self.durability = self.durability.min(other.durability);
+        self.lower_durability(other.durability, other.durability_lowered_by);
         self.untracked_read |= other.untracked_read;
-        self.input_outputs
-            .extend(other.input_outputs.iter().copied());
+        self.input_outputs.extend(
+            other
+                .input_outputs
+                .iter()
+                .filter(|(edge_kind, _)| *edge_kind == EdgeKind::Input)
+                .copied(),
+        );
     }
 
     /// Removes the participants in `cycle` from my dependencies.
@@ -147,21 +211,90 @@ impl ActiveQuery {
         }
     }
 
-    /// Copy the changed-at, durability, and dependencies from `cycle_query`.
+    /// Copy the changed-at, durability (with the input that lowered it), and dependencies
+    /// from `cycle_query`.
     /// Used during cycle recovery, see [`Runtime::unblock_cycle_and_maybe_throw`].
+    ///
+    /// The edges of this query are kept (in order) and the inputs of the other
+    /// participants are added after them. In particular, the outputs of this query
+    /// (e.g., tracked structs it created or values it specified) stay with this query
+    /// and are still validated before any input that may read them.
     pub(crate) fn take_inputs_from(&mut self, cycle_query: &ActiveQuery) {
         self.changed_at = cycle_query.changed_at;
         self.durability = cycle_query.durability;
-        self.input_outputs.clone_from(&cycle_query.input_outputs);
+        self.durability_lowered_by = cycle_query.durability_lowered_by;
+        self.input_outputs
+            .extend(cycle_query.input_outputs.iter().copied());
     }
 
-    pub(super) fn disambiguate(&mut self, key: IdentityHash) -> Disambiguator {
-        let disambiguator = self
-            .disambiguator_map
-            .entry(key)
-            .or_insert(Disambiguator(0));
-        let result = *disambiguator;
-        disambiguator.0 += 1;
-        result
+    /// Adds the dependencies and outputs of `task`, a task run in parallel by this query
+    /// (see [`crate::parallel`]), after its own.
+    pub(crate) fn add_task(&mut self, task: ActiveQuery) {
+        self.changed_at = self.changed_at.max(task.changed_at);
+        self.lower_durability(task.durability, task.durability_lowered_by);
+        self.untracked_read |= task.untracked_read;
+        self.input_outputs.extend(task.input_outputs);
+        self.accumulated.append(task.accumulated);
     }
+
+    /// Starts a parallel operation, returning its key (see [`TaskKey`]). Moves the tracked struct
+    /// identities of this query to a [`SharedIdentities`], if not done already, to be used
+    /// by the tasks it runs in parallel. They stay there until the query completes.
+    pub(crate) fn start_operation(&mut self) -> (TaskKey, Arc<Mutex<SharedIdentities>>) {
+        let operation = self.task.child(self.operations);
+        self.operations += 1;
+        let shared = self.shared_identities.get_or_insert_with(|| {
+            Arc::new(Mutex::new(SharedIdentities {
+                disambiguator_map: std::mem::take(&mut self.disambiguator_map),
+                tracked_struct_ids: std::mem::take(&mut self.tracked_struct_ids),
+            }))
+        });
+        (operation, shared.clone())
+    }
+
+    /// Makes this query the parallel `task` of another query, using the tracked struct
+    /// identities of that query.
+    pub(crate) fn use_shared_identities(
+        &mut self,
+        task: TaskKey,
+        shared: Arc<Mutex<SharedIdentities>>,
+    ) {
+        self.task = task;
+        self.shared_identities = Some(shared);
+    }
+
+    /// Returns the identity of a tracked struct with hash `key` created by this query.
+    pub(super) fn disambiguate(&mut self, key: IdentityHash) -> Identity {
+        let key = key.for_task(self.task);
+        let disambiguator = match &self.shared_identities {
+            Some(shared) => next_disambiguator(&mut shared.lock().disambiguator_map, key),
+            None => next_disambiguator(&mut self.disambiguator_map, key),
+        };
+        Identity::new(key, disambiguator)
+    }
+
+    pub(super) fn tracked_struct_id(&self, identity: &Identity) -> Option<Id> {
+        match &self.shared_identities {
+            Some(shared) => shared.lock().tracked_struct_ids.get(identity).copied(),
+            None => self.tracked_struct_ids.get(identity).copied(),
+        }
+    }
+
+    /// Stores the id of a tracked struct, returning the previous id for `identity`, if any.
+    pub(super) fn store_tracked_struct_id(&mut self, identity: Identity, id: Id) -> Option<Id> {
+        match &self.shared_identities {
+            Some(shared) => shared.lock().tracked_struct_ids.insert(identity, id),
+            None => self.tracked_struct_ids.insert(identity, id),
+        }
+    }
+}
+
+fn next_disambiguator(
+    disambiguator_map: &mut FxHashMap<IdentityHash, Disambiguator>,
+    key: IdentityHash,
+) -> Disambiguator {
+    let disambiguator = disambiguator_map.entry(key).or_insert(Disambiguator(0));
+    let result = *disambiguator;
+    disambiguator.0 += 1;
+    result
 }
@@ -71,6 +71,27 @@ impl Attached {
         op()
     }
 
+    /// Attaches `db` in place of the attached database, if any, while `op` runs.
+    fn attach_in_place<R>(&self, db: &dyn Database, op: impl FnOnce() -> R) -> R {
+        struct Restore<'s> {
+            attached: &'s Attached,
+            old: Option<NonNull<dyn Database>>,
+        }
+
+        impl Drop for Restore<'_> {
+            fn drop(&mut self) {
+                self.attached.database.set(self.old);
+            }
+        }
+
+        let old = self.database.replace(Some(NonNull::from(db)));
+        let _restore = Restore {
+            attached: self,
+            old,
+        };
+        op()
+    }
+
     /// Access the "attached" database. Returns `None` if no database is attached.
     /// Databases are attached with `attach_database`.
     fn with<R>(&self, op: impl FnOnce(&dyn Database) -> R) -> Option<R> {
@@ -91,6 +112,12 @@ where
     ATTACHED.with(|a| a.attach(db, op))
 }
 
+/// Attach `db`, a handle forked from the attached database (if any), in its place
+/// while `op` runs. Used by tasks that an executor runs on the current thread.
+pub(crate) fn attach_fork<R>(db: &dyn Database, op: impl FnOnce() -> R) -> R {
+    ATTACHED.with(|a| a.attach_in_place(db, op))
+}
+
 /// Access the "attached" database. Returns `None` if no database is attached.
 /// Databases are attached with `attach_database`.
 pub fn with_attached_database<R>(op: impl FnOnce(&dyn Database) -> R) -> Option<R> {
@@ -3,6 +3,8 @@ use std::{
     panic::{self, UnwindSafe},
 };
 
+use crate::QueryStack;
+
 /// A panic payload indicating that execution of a salsa query was cancelled.
 ///
 /// This can occur for a few reasons:
@@ -18,7 +20,10 @@ pub enum Cancelled {
 
     /// The query was blocked on another thread, and that thread panicked.
     #[non_exhaustive]
-    PropagatedPanic,
+    PropagatedPanic {
+        /// The query stack of the panicking thread, at the point of the panic.
+        query_stack: QueryStack,
+    },
 }
 
 impl Cancelled {
@@ -47,7 +52,7 @@ impl std::fmt::Display for Cancelled {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let why = match self {
             Cancelled::PendingWrite => "pending write",
-            Cancelled::PropagatedPanic => "propagated panic",
+            Cancelled::PropagatedPanic { .. } => "propagated panic",
         };
         f.write_str("cancelled because of ")?;
         f.write_str(why)
@@ -1,5 +1,7 @@
 use crate::{key::DatabaseKeyIndex, Database};
-use std::{panic::AssertUnwindSafe, sync::Arc};
+use std::{
+    cmp::Ordering, fmt::Write, hash::Hash, panic::AssertUnwindSafe, sync::Arc, thread::ThreadId,
+};
 
 /// Captures the participants of a cycle that occurred when executing a query.
 ///
@@ -14,18 +16,61 @@ use std::{panic::AssertUnwindSafe, sync::Arc};
 /// * As the panic value when an unexpected cycle (i.e., a cycle where one or more participants
 ///   lacks cycle recovery information) occurs.
 ///
+/// To report a cycle to the user, use [`Cycle::render`], or walk the
+/// [`edges`](`Cycle::edges`) of the cycle.
+///
 /// You can read more about cycle handling in
 /// the [salsa book](https://https://salsa-rs.github.io/salsa/cycles.html).
-#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+///
+/// Two cycles are equal if they have the same participants
+/// (the threads executing them are not compared).
+#[derive(Clone)]
 pub struct Cycle {
     participants: CycleParticipants,
+
+    /// The thread executing each participant.
+    threads: Arc<[ThreadId]>,
+
+    /// The participant that was re-entered, closing the cycle.
+    head: DatabaseKeyIndex,
 }
 
 pub(crate) type CycleParticipants = Arc<Vec<DatabaseKeyIndex>>;
 
+/// An edge of a [`Cycle`]: the query `from` depends directly on the query `to`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CycleEdge {
+    pub from: DatabaseKeyIndex,
+    pub to: DatabaseKeyIndex,
+
+    /// The thread executing `from`.
+    pub from_thread: ThreadId,
+
+    /// The thread executing `to`. If it differs from `from_thread`,
+    /// then `from_thread` was blocked waiting for `to`.
+    pub to_thread: ThreadId,
+}
+
+impl CycleEdge {
+    /// True if the edge goes from one thread to another.
+    pub fn crosses_threads(&self) -> bool {
+        self.from_thread != self.to_thread
+    }
+}
+
 impl Cycle {
-    pub(crate) fn new(participants: CycleParticipants) -> Self {
-        Self { participants }
+    pub(crate) fn new(
+        participants: CycleParticipants,
+        threads: Arc<[ThreadId]>,
+        head: DatabaseKeyIndex,
+    ) -> Self {
+        assert_eq!(participants.len(), threads.len());
+        debug_assert!(participants.contains(&head));
+        Self {
+            participants,
+            threads,
+            head,
+        }
     }
 
     /// True if two `Cycle` values represent the same cycle.
@@ -56,6 +101,44 @@ impl Cycle {
         self.participants.iter().copied()
     }
 
+    /// Iterate over the edges of the cycle, in execution order: each participant
+    /// depends on the next one, and the last one depends on the first one.
+    /// The iteration starts at the same participant as [`Cycle::participant_keys`].
+    pub fn edges(&self) -> impl Iterator<Item = CycleEdge> + '_ {
+        let len = self.participants.len();
+        (0..len).map(move |i| {
+            let next = (i + 1) % len;
+            CycleEdge {
+                from: self.participants[i],
+                to: self.participants[next],
+                from_thread: self.threads[i],
+                to_thread: self.threads[next],
+            }
+        })
+    }
+
+    /// Formats the path of the cycle for an error message, e.g.
+    /// `a(Id(0)) -> b(Id(1)) => c(Id(2)) -> a(Id(0))`.
+    ///
+    /// Each participant is formatted with its ingredient name and key, and the path
+    /// returns to the first participant. Edges that cross threads are rendered as `=>`,
+    /// unless the storage uses [deterministic execution](`crate::Storage::with_deterministic_execution`).
+    pub fn render(&self, db: &dyn Database) -> String {
+        let mark_threads = !db.zalsa().is_deterministic();
+        crate::attach::attach(db, || {
+            let mut rendered = format!("{:?}", self.participants[0]);
+            for edge in self.edges() {
+                let arrow = if mark_threads && edge.crosses_threads() {
+                    "=>"
+                } else {
+                    "->"
+                };
+                write!(rendered, " {arrow} {:?}", edge.to).unwrap();
+            }
+            rendered
+        })
+    }
+
     /// Returns a vector with the debug information for
     /// all the participants in the cycle.
     pub fn all_participants(&self, _db: &dyn Database) -> Vec<DatabaseKeyIndex> {
@@ -88,6 +171,122 @@ impl std::fmt::Debug for Cycle {
     }
 }
 
+impl PartialEq for Cycle {
+    fn eq(&self, other: &Self) -> bool {
+        self.participants == other.participants
+    }
+}
+
+impl Eq for Cycle {}
+
+impl PartialOrd for Cycle {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Cycle {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.participants.cmp(&other.participants)
+    }
+}
+
+impl Hash for Cycle {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.participants.hash(state);
+    }
+}
+
+/// The context given to the recovery function of a cycle participant
+/// (see [cycle recovery](https://salsa-rs.github.io/salsa/cycles/fallback.html)).
+///
+/// Dereferences to the [`Cycle`], so recovery functions may also take a `&Cycle`.
+#[derive(Clone, Debug)]
+pub struct CycleContext {
+    cycle: Cycle,
+
+    /// The participant that is recovering.
+    key: DatabaseKeyIndex,
+
+    /// The position of `key` in the participants of the cycle.
+    position: usize,
+
+    /// For each participant, whether it has cycle recovery.
+    recovers: Vec<bool>,
+}
+
+impl CycleContext {
+    pub(crate) fn new(db: &dyn Database, cycle: Cycle, key: DatabaseKeyIndex) -> Self {
+        let position = cycle
+            .participant_keys()
+            .position(|k| k == key)
+            .expect("recovering query is not a cycle participant");
+        let recovers = cycle
+            .participant_keys()
+            .map(|k| k.cycle_recovery_strategy(db) == CycleRecoveryStrategy::Fallback)
+            .collect();
+        Self {
+            cycle,
+            key,
+            position,
+            recovers,
+        }
+    }
+
+    /// The cycle.
+    pub fn cycle(&self) -> &Cycle {
+        &self.cycle
+    }
+
+    /// The participant that is recovering.
+    pub fn key(&self) -> DatabaseKeyIndex {
+        self.key
+    }
+
+    /// The position of [`CycleContext::key`] in [`Cycle::participant_keys`].
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// The head of the cycle: the participant that was executing when the
+    /// cycle was entered, and that was called again to close the cycle.
+    ///
+    /// With [deterministic execution](`crate::Storage::with_deterministic_execution`),
+    /// this is the first of the [`Cycle::participant_keys`] instead.
+    pub fn head(&self) -> DatabaseKeyIndex {
+        self.cycle.head
+    }
+
+    /// True if the recovering participant is the head of the cycle.
+    pub fn is_head(&self) -> bool {
+        self.key == self.cycle.head
+    }
+
+    /// True if `key` participates in the cycle and has cycle recovery.
+    pub fn has_recovery(&self, key: DatabaseKeyIndex) -> bool {
+        self.cycle
+            .participant_keys()
+            .zip(&self.recovers)
+            .any(|(k, &recovers)| k == key && recovers)
+    }
+
+    /// Iterate over the participants of the cycle (in the order of [`Cycle::participant_keys`])
+    /// along with whether each of them has cycle recovery.
+    pub fn participants(&self) -> impl Iterator<Item = (DatabaseKeyIndex, bool)> + '_ {
+        self.cycle
+            .participant_keys()
+            .zip(self.recovers.iter().copied())
+    }
+}
+
+impl std::ops::Deref for CycleContext {
+    type Target = Cycle;
+
+    fn deref(&self) -> &Cycle {
+        &self.cycle
+    }
+}
+
 /// Cycle recovery strategy: Is this query capable of recovering from
 /// a cycle that results from executing the function? If so, how?
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
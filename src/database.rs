@@ -1,8 +1,9 @@
 use std::{any::Any, borrow::Cow};
 
 use crate::{
+    key::DatabaseKeyIndex,
     zalsa::{IngredientIndex, ZalsaDatabase},
-    Durability, Event, Revision,
+    Durability, Event, EventKindMask, Revision, WaitForGraph,
 };
 
 /// The trait implemented by all Salsa databases.
@@ -18,6 +19,15 @@ pub trait Database: Send + AsDynDatabase + Any + ZalsaDatabase {
     /// * `event`, a fn that, if called, will create the event that occurred
     fn salsa_event(&self, event: &dyn Fn() -> Event);
 
+    /// The kinds of events to pass to [`Database::salsa_event`]; defaults to all of them.
+    /// Events of other kinds are not even constructed, which makes frequent events such as
+    /// [`WillCheckCancellation`](`crate::EventKind::WillCheckCancellation`) free when ignored.
+    ///
+    /// This is queried once, when the first event occurs, and must not change afterwards.
+    fn salsa_event_filter(&self) -> EventKindMask {
+        EventKindMask::ALL
+    }
+
     /// A "synthetic write" causes the system to act *as though* some
     /// input of durability `durability` has changed. This is mostly
     /// useful for profiling scenarios.
@@ -31,6 +41,50 @@ pub trait Database: Send + AsDynDatabase + Any + ZalsaDatabase {
         zalsa_mut.report_tracked_write(durability);
     }
 
+    /// Freezes the database, making it immutable: after this, setting an input
+    /// (or anything else that would start a new revision) panics. This applies
+    /// to all handles to this database, including one that is already waiting
+    /// for the other handles to be dropped to start a new revision.
+    ///
+    /// This is useful for batch runs that set their inputs once and then execute
+    /// many queries. In a frozen database, queries skip cancellation checks
+    /// (hence no [`WillCheckCancellation`](`crate::EventKind::WillCheckCancellation`) events
+    /// are emitted), and memos that have been computed or verified since freezing are
+    /// returned without any verification. A memo from before freezing is still verified
+    /// once, with a walk over its dependencies if inputs changed since it was last verified.
+    fn freeze(&mut self) {
+        self.zalsa().freeze();
+    }
+
+    /// True if [`Database::freeze`] has been called on this database.
+    fn is_frozen(&self) -> bool {
+        self.zalsa().is_frozen()
+    }
+
+    /// The keys of the tracked functions currently executing on this thread, outermost first.
+    ///
+    /// Inspecting the stack does not record a dependency.
+    fn active_query_stack(&self) -> Vec<DatabaseKeyIndex> {
+        self.zalsa_local().active_query_keys()
+    }
+
+    /// The key of the innermost tracked function executing on this thread,
+    /// or `None` outside of tracked functions.
+    ///
+    /// Like [`Database::active_query_stack`], this does not record a dependency.
+    fn current_query(&self) -> Option<DatabaseKeyIndex> {
+        self.zalsa_local()
+            .active_query()
+            .map(|(database_key_index, _)| database_key_index)
+    }
+
+    /// Returns a snapshot of the threads that are currently blocked on queries
+    /// executing on other threads, which helps to diagnose hangs in parallel code.
+    /// See also [`Storage::with_watchdog`](`crate::Storage::with_watchdog`).
+    fn wait_for_graph(&self) -> WaitForGraph {
+        self.zalsa().wait_for_graph()
+    }
+
     /// Reports that the query depends on some state unknown to salsa.
     ///
     /// Queries which report untracked reads will be re-executed in the next
@@ -1,3 +1,5 @@
+use crate::key::DependencyIndex;
+
 /// Describes how likely a value is to change—how "durable" it is.
 ///
 /// By default, inputs have `Durability::LOW` and interned values have
@@ -36,19 +38,89 @@ impl Durability {
     /// Example: the standard library or something from crates.io
     pub const HIGH: Durability = Durability(2);
 
-    /// The maximum possible durability; equivalent to HIGH but
-    /// "conceptually" distinct (i.e., if we add more durability
-    /// levels, this could change).
-    pub(crate) const MAX: Durability = Self::HIGH;
+    /// Creates the durability with the given level. Higher levels are more durable.
+    ///
+    /// `LOW`, `MEDIUM`, and `HIGH` are levels 0, 1, and 2. A database has three
+    /// durability levels by default; to distinguish more of them, create its storage
+    /// with [`Storage::with_durabilities`](`crate::Storage::with_durabilities`).
+    /// Levels beyond those configured for the database are treated like the highest
+    /// configured level.
+    ///
+    /// Typically, you would define named constants for the levels you use:
+    ///
+    /// ```
+    /// # use salsa::Durability;
+    /// const WORKSPACE: Durability = Durability::new(0);
+    /// const VENDORED: Durability = Durability::new(1);
+    /// const TOOLCHAIN: Durability = Durability::new(2);
+    /// const GENERATED: Durability = Durability::new(3);
+    /// const CONFIG: Durability = Durability::new(4);
+    /// ```
+    pub const fn new(level: u8) -> Durability {
+        Durability(level)
+    }
 
-    /// Number of durability levels.
-    pub(crate) const LEN: usize = 3;
+    /// The level of this durability, as given to [`Durability::new`].
+    pub const fn level(self) -> u8 {
+        self.0
+    }
+
+    /// Default number of durability levels (`LOW`, `MEDIUM`, and `HIGH`).
+    pub(crate) const DEFAULT_LEVELS: usize = 3;
+
+    /// Maximum number of durability levels.
+    pub(crate) const MAX_LEVELS: usize = u8::MAX as usize + 1;
 
     pub(crate) fn index(self) -> usize {
         self.0 as usize
     }
 }
 
+/// The durability of a memoized value and the input responsible for it.
+///
+/// Returned by the `durability_info` function generated for each tracked function,
+/// this helps to find out why a memo has a lower durability than expected.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DurabilityInfo {
+    /// The durability of the memoized value: the minimum durability of its inputs.
+    pub durability: Durability,
+
+    /// The first input read by the function that has durability `durability`.
+    /// If that input is another tracked function, its own `durability_info`
+    /// tells which input lowered its durability in turn.
+    ///
+    /// `None` if the function read no inputs, or if its durability was lowered
+    /// by an untracked read. Only recorded in debug builds: always `None` in release builds.
+    pub lowered_by: Option<DependencyIndex>,
+}
+
+/// The input that lowered the durability of a query, see [`DurabilityInfo::lowered_by`].
+/// Only recorded in debug builds, so that it costs nothing in release builds.
+#[derive(Copy, Clone, Debug, Default)]
+pub(crate) struct LoweredBy {
+    #[cfg(debug_assertions)]
+    input: Option<DependencyIndex>,
+}
+
+impl LoweredBy {
+    pub(crate) fn new(input: Option<DependencyIndex>) -> Self {
+        #[cfg(not(debug_assertions))]
+        let _ = input;
+        Self {
+            #[cfg(debug_assertions)]
+            input,
+        }
+    }
+
+    pub(crate) fn get(self) -> Option<DependencyIndex> {
+        #[cfg(debug_assertions)]
+        return self.input;
+
+        #[cfg(not(debug_assertions))]
+        return None;
+    }
+}
+
 impl Default for Durability {
     fn default() -> Self {
         Durability::LOW
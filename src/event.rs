@@ -1,6 +1,6 @@
 use std::thread::ThreadId;
 
-use crate::{key::DatabaseKeyIndex, key::DependencyIndex};
+use crate::{key::DatabaseKeyIndex, key::DependencyIndex, Durability, Revision};
 
 /// The `Event` struct identifies various notable things that can
 /// occur during salsa execution. Instances of this struct are given
@@ -81,4 +81,166 @@ pub enum EventKind {
         /// Accumulator that was accumulated into
         accumulator: DependencyIndex,
     },
+
+    /// A memo with a new value (computed or specified) was stored.
+    ///
+    /// Executes after any backdating, so `changed_at` is final.
+    DidStoreMemo {
+        /// The database-key for the affected value. Implements `Debug`.
+        database_key: DatabaseKeyIndex,
+
+        /// The revision in which the value last changed.
+        changed_at: Revision,
+
+        /// The durability of the value.
+        durability: Durability,
+    },
+
+    /// A recomputed value was equal to the old one, so its `changed_at`
+    /// revision was set back to the one of the old value.
+    DidBackdateValue {
+        /// The database-key for the affected value. Implements `Debug`.
+        database_key: DatabaseKeyIndex,
+
+        /// The revision in which the value last changed.
+        changed_at: Revision,
+    },
+
+    /// The LRU evicted the value of a memo (its dependencies are kept).
+    DidEvictValue {
+        /// The database-key for the affected value. Implements `Debug`.
+        database_key: DatabaseKeyIndex,
+    },
+
+    /// A new revision was started, after all other handles were cancelled.
+    DidStartNewRevision {
+        /// The new current revision.
+        revision: Revision,
+    },
+
+    /// A field of an input was set.
+    DidSetInputField {
+        /// Key for the input field. Implements `Debug`.
+        key: DatabaseKeyIndex,
+
+        /// The durability of the new value.
+        durability: Durability,
+    },
+
+    /// A new value was interned.
+    DidInternValue {
+        /// Key for the interned value. Implements `Debug`.
+        key: DatabaseKeyIndex,
+    },
+}
+
+impl EventKind {
+    /// The mask with just the bit for this kind of event.
+    pub fn mask(&self) -> EventKindMask {
+        match self {
+            EventKind::DidValidateMemoizedValue { .. } => {
+                EventKindMask::DID_VALIDATE_MEMOIZED_VALUE
+            }
+            EventKind::WillBlockOn { .. } => EventKindMask::WILL_BLOCK_ON,
+            EventKind::WillExecute { .. } => EventKindMask::WILL_EXECUTE,
+            EventKind::WillCheckCancellation => EventKindMask::WILL_CHECK_CANCELLATION,
+            EventKind::DidSetCancellationFlag => EventKindMask::DID_SET_CANCELLATION_FLAG,
+            EventKind::WillDiscardStaleOutput { .. } => EventKindMask::WILL_DISCARD_STALE_OUTPUT,
+            EventKind::DidDiscard { .. } => EventKindMask::DID_DISCARD,
+            EventKind::DidDiscardAccumulated { .. } => EventKindMask::DID_DISCARD_ACCUMULATED,
+            EventKind::DidStoreMemo { .. } => EventKindMask::DID_STORE_MEMO,
+            EventKind::DidBackdateValue { .. } => EventKindMask::DID_BACKDATE_VALUE,
+            EventKind::DidEvictValue { .. } => EventKindMask::DID_EVICT_VALUE,
+            EventKind::DidStartNewRevision { .. } => EventKindMask::DID_START_NEW_REVISION,
+            EventKind::DidSetInputField { .. } => EventKindMask::DID_SET_INPUT_FIELD,
+            EventKind::DidInternValue { .. } => EventKindMask::DID_INTERN_VALUE,
+        }
+    }
+}
+
+/// A set of [`EventKind`]s, with one bit per kind.
+///
+/// Returned by [`Database::salsa_event_filter`](`crate::Database::salsa_event_filter`)
+/// to select the events that are passed to `salsa_event`.
+/// Masks can be combined with `|`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct EventKindMask(u32);
+
+impl EventKindMask {
+    /// No events.
+    pub const NONE: EventKindMask = EventKindMask(0);
+
+    /// All events.
+    pub const ALL: EventKindMask = EventKindMask(u32::MAX);
+
+    /// See [`EventKind::DidValidateMemoizedValue`].
+    pub const DID_VALIDATE_MEMOIZED_VALUE: EventKindMask = EventKindMask(1 << 0);
+
+    /// See [`EventKind::WillBlockOn`].
+    pub const WILL_BLOCK_ON: EventKindMask = EventKindMask(1 << 1);
+
+    /// See [`EventKind::WillExecute`].
+    pub const WILL_EXECUTE: EventKindMask = EventKindMask(1 << 2);
+
+    /// See [`EventKind::WillCheckCancellation`].
+    pub const WILL_CHECK_CANCELLATION: EventKindMask = EventKindMask(1 << 3);
+
+    /// See [`EventKind::DidSetCancellationFlag`].
+    pub const DID_SET_CANCELLATION_FLAG: EventKindMask = EventKindMask(1 << 4);
+
+    /// See [`EventKind::WillDiscardStaleOutput`].
+    pub const WILL_DISCARD_STALE_OUTPUT: EventKindMask = EventKindMask(1 << 5);
+
+    /// See [`EventKind::DidDiscard`].
+    pub const DID_DISCARD: EventKindMask = EventKindMask(1 << 6);
+
+    /// See [`EventKind::DidDiscardAccumulated`].
+    pub const DID_DISCARD_ACCUMULATED: EventKindMask = EventKindMask(1 << 7);
+
+    /// See [`EventKind::DidStoreMemo`].
+    pub const DID_STORE_MEMO: EventKindMask = EventKindMask(1 << 8);
+
+    /// See [`EventKind::DidBackdateValue`].
+    pub const DID_BACKDATE_VALUE: EventKindMask = EventKindMask(1 << 9);
+
+    /// See [`EventKind::DidEvictValue`].
+    pub const DID_EVICT_VALUE: EventKindMask = EventKindMask(1 << 10);
+
+    /// See [`EventKind::DidStartNewRevision`].
+    pub const DID_START_NEW_REVISION: EventKindMask = EventKindMask(1 << 11);
+
+    /// See [`EventKind::DidSetInputField`].
+    pub const DID_SET_INPUT_FIELD: EventKindMask = EventKindMask(1 << 12);
+
+    /// See [`EventKind::DidInternValue`].
+    pub const DID_INTERN_VALUE: EventKindMask = EventKindMask(1 << 13);
+
+    /// The kinds in either `self` or `other`.
+    pub const fn union(self, other: EventKindMask) -> EventKindMask {
+        EventKindMask(self.0 | other.0)
+    }
+
+    /// The kinds in `self` but not in `other`.
+    pub const fn difference(self, other: EventKindMask) -> EventKindMask {
+        EventKindMask(self.0 & !other.0)
+    }
+
+    /// True if all kinds in `other` are also in `self`.
+    pub const fn contains(self, other: EventKindMask) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl std::ops::BitOr for EventKindMask {
+    type Output = EventKindMask;
+
+    fn bitor(self, other: EventKindMask) -> EventKindMask {
+        self.union(other)
+    }
+}
+
+impl std::fmt::Debug for EventKindMask {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "EventKindMask({:#x})", self.0)
+    }
 }
@@ -0,0 +1,267 @@
+use std::io::{self, BufRead, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::{key::DependencyIndex, Database, Durability, Event, EventKind, Revision};
+
+/// Records [`Event`]s to a log, one JSON object per line.
+///
+/// Ingredient indices are resolved to their debug names when recording,
+/// so the log can be analyzed without access to the database.
+/// Call [`EventRecorder::record`] from `salsa_event` and read the log back
+/// with [`RecordedEvent::read_log`]. Requires the `event-log` feature.
+pub struct EventRecorder {
+    out: Mutex<Box<dyn Write + Send>>,
+    seq: AtomicU64,
+}
+
+impl EventRecorder {
+    pub fn new(out: impl Write + Send + 'static) -> Self {
+        Self {
+            out: Mutex::new(Box::new(out)),
+            seq: AtomicU64::new(0),
+        }
+    }
+
+    /// Append `event` to the log.
+    pub fn record(&self, db: &dyn Database, event: &Event) -> io::Result<()> {
+        let mut out = self.out.lock();
+        // Assign the sequence number while holding the lock,
+        // so that lines in the log are ordered by `seq`.
+        let seq = self.seq.fetch_add(1, Ordering::Relaxed);
+        let line = RecordedEvent::from_event(db, seq, event).to_json();
+        writeln!(out, "{line}")
+    }
+
+    pub fn flush(&self) -> io::Result<()> {
+        self.out.lock().flush()
+    }
+}
+
+impl std::fmt::Debug for EventRecorder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventRecorder")
+            .field("seq", &self.seq)
+            .finish_non_exhaustive()
+    }
+}
+
+/// An [`Event`] as it is stored in the log written by [`EventRecorder`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    /// Position of the event in the log.
+    pub seq: u64,
+
+    /// The current revision when the event occurred.
+    pub revision: u64,
+
+    /// The thread that triggered the event, formatted with `Debug`.
+    pub thread: String,
+
+    /// The kind of the event.
+    pub kind: RecordedEventKind,
+
+    /// The key the event is about, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<RecordedKey>,
+
+    /// The stale output of `WillDiscardStaleOutput`,
+    /// or the accumulator of `DidDiscardAccumulated`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub other_key: Option<RecordedKey>,
+
+    /// The thread blocked on by `WillBlockOn`, formatted with `Debug`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub other_thread: Option<String>,
+
+    /// The revision in which the value last changed, if the event has one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changed_at: Option<u64>,
+
+    /// The durability level, if the event has one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub durability: Option<u8>,
+}
+
+/// The kind of a [`RecordedEvent`]: the variant of its [`EventKind`], without the fields.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum RecordedEventKind {
+    DidValidateMemoizedValue,
+    WillBlockOn,
+    WillExecute,
+    WillCheckCancellation,
+    DidSetCancellationFlag,
+    WillDiscardStaleOutput,
+    DidDiscard,
+    DidDiscardAccumulated,
+    DidStoreMemo,
+    DidBackdateValue,
+    DidEvictValue,
+    DidStartNewRevision,
+    DidSetInputField,
+    DidInternValue,
+}
+
+/// A [`DependencyIndex`] with its ingredient resolved to a debug name.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedKey {
+    pub ingredient: String,
+    pub ingredient_index: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<u32>,
+}
+
+impl std::fmt::Display for RecordedKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.id {
+            Some(id) => write!(f, "{}({id})", self.ingredient),
+            None => write!(f, "{}", self.ingredient),
+        }
+    }
+}
+
+impl RecordedKey {
+    fn new(db: &dyn Database, index: impl Into<DependencyIndex>) -> Self {
+        let index = index.into();
+        Self {
+            ingredient: db
+                .ingredient_debug_name(index.ingredient_index)
+                .into_owned(),
+            ingredient_index: index.ingredient_index.as_usize() as u32,
+            id: index.key_index.map(|id| id.as_u32()),
+        }
+    }
+}
+
+fn revision_number(revision: Revision) -> u64 {
+    revision.as_usize() as u64
+}
+
+fn durability_level(durability: Durability) -> u8 {
+    durability.level()
+}
+
+impl RecordedEvent {
+    pub fn from_event(db: &dyn Database, seq: u64, event: &Event) -> Self {
+        let mut recorded_revision = revision_number(db.zalsa().current_revision());
+        let (mut recorded_key, mut other_key, mut other_thread) = (None, None, None);
+        let (mut changed_at_number, mut durability_number) = (None, None);
+        let kind = match event.kind {
+            EventKind::DidValidateMemoizedValue { database_key } => {
+                recorded_key = Some(RecordedKey::new(db, database_key));
+                RecordedEventKind::DidValidateMemoizedValue
+            }
+            EventKind::WillBlockOn {
+                other_thread_id,
+                database_key,
+            } => {
+                recorded_key = Some(RecordedKey::new(db, database_key));
+                other_thread = Some(format!("{other_thread_id:?}"));
+                RecordedEventKind::WillBlockOn
+            }
+            EventKind::WillExecute { database_key } => {
+                recorded_key = Some(RecordedKey::new(db, database_key));
+                RecordedEventKind::WillExecute
+            }
+            EventKind::WillCheckCancellation => RecordedEventKind::WillCheckCancellation,
+            EventKind::DidSetCancellationFlag => RecordedEventKind::DidSetCancellationFlag,
+            EventKind::WillDiscardStaleOutput {
+                execute_key,
+                output_key,
+            } => {
+                recorded_key = Some(RecordedKey::new(db, execute_key));
+                other_key = Some(RecordedKey::new(db, output_key));
+                RecordedEventKind::WillDiscardStaleOutput
+            }
+            EventKind::DidDiscard { key } => {
+                recorded_key = Some(RecordedKey::new(db, key));
+                RecordedEventKind::DidDiscard
+            }
+            EventKind::DidDiscardAccumulated {
+                executor_key,
+                accumulator,
+            } => {
+                recorded_key = Some(RecordedKey::new(db, executor_key));
+                other_key = Some(RecordedKey::new(db, accumulator));
+                RecordedEventKind::DidDiscardAccumulated
+            }
+            EventKind::DidStoreMemo {
+                database_key,
+                changed_at,
+                durability,
+            } => {
+                recorded_key = Some(RecordedKey::new(db, database_key));
+                changed_at_number = Some(revision_number(changed_at));
+                durability_number = Some(durability_level(durability));
+                RecordedEventKind::DidStoreMemo
+            }
+            EventKind::DidBackdateValue {
+                database_key,
+                changed_at,
+            } => {
+                recorded_key = Some(RecordedKey::new(db, database_key));
+                changed_at_number = Some(revision_number(changed_at));
+                RecordedEventKind::DidBackdateValue
+            }
+            EventKind::DidEvictValue { database_key } => {
+                recorded_key = Some(RecordedKey::new(db, database_key));
+                RecordedEventKind::DidEvictValue
+            }
+            EventKind::DidStartNewRevision { revision } => {
+                recorded_revision = revision_number(revision);
+                RecordedEventKind::DidStartNewRevision
+            }
+            EventKind::DidSetInputField { key, durability } => {
+                recorded_key = Some(RecordedKey::new(db, key));
+                durability_number = Some(durability_level(durability));
+                RecordedEventKind::DidSetInputField
+            }
+            EventKind::DidInternValue { key } => {
+                recorded_key = Some(RecordedKey::new(db, key));
+                RecordedEventKind::DidInternValue
+            }
+        };
+        RecordedEvent {
+            seq,
+            revision: recorded_revision,
+            thread: format!("{:?}", event.thread_id),
+            kind,
+            key: recorded_key,
+            other_key,
+            other_thread,
+            changed_at: changed_at_number,
+            durability: durability_number,
+        }
+    }
+
+    /// Formats the event as a single-line JSON object. Fields that are `None` are omitted.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("events can always be serialized")
+    }
+
+    /// Parses a line written by [`EventRecorder`].
+    pub fn parse(line: &str) -> io::Result<Self> {
+        Ok(serde_json::from_str(line)?)
+    }
+
+    /// Read a log written by [`EventRecorder`], skipping empty lines.
+    pub fn read_log(reader: impl BufRead) -> io::Result<Vec<RecordedEvent>> {
+        let mut events = vec![];
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let event = Self::parse(&line).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: {e}", index + 1),
+                )
+            })?;
+            events.push(event);
+        }
+        Ok(events)
+    }
+}
@@ -0,0 +1,159 @@
+/// A task run by an [`Executor`].
+pub type Task<'a> = Box<dyn FnOnce() + Send + 'a>;
+
+/// The operation run by [`Executor::scope`], which spawns tasks with the function it is called with.
+pub type ScopeOp<'a, 'scope> = Box<dyn FnOnce(&dyn Fn(Task<'scope>)) + 'a>;
+
+/// Runs the tasks of the parallel operations of a database: [`join`](`crate::join`),
+/// [`scope`](`crate::scope`), [`spawn`](`crate::spawn`), [`par_map`](`crate::par_map`) and its
+/// variants, and parallel verification
+/// (see [`Storage::with_parallel_verification`](`crate::Storage::with_parallel_verification`)).
+///
+/// A database uses the executor configured with [`Storage::with_executor`](`crate::Storage::with_executor`).
+/// By default, it is a [`RayonExecutor`] on the global rayon thread pool if the `rayon` feature
+/// is enabled (which it is by default), and otherwise an executor that runs all tasks one after
+/// the other on the current thread.
+///
+/// Each task uses a handle to the database of its own, so tasks can run on any thread.
+/// If a task panics, the executor must propagate the panic to the caller
+/// (e.g., with [`std::panic::resume_unwind`]), preferably once the other tasks have completed.
+pub trait Executor: Send + Sync + 'static {
+    /// Runs `a` and `b`, potentially in parallel, and returns once both have completed.
+    fn join(&self, a: Task<'_>, b: Task<'_>);
+
+    /// Calls `op` with each index in `0..len`, potentially in parallel,
+    /// and returns once all calls have completed.
+    fn for_each(&self, len: usize, op: &(dyn Fn(usize) + Sync));
+
+    /// Calls `op` on the current thread with a function that spawns tasks,
+    /// and returns once `op` and all spawned tasks have completed.
+    fn scope<'scope>(&self, op: ScopeOp<'_, 'scope>);
+
+    /// Runs `task`, potentially in parallel, without waiting for it to complete.
+    /// There is no caller to propagate a panic of `task` to: the executor handles it as it sees fit.
+    fn spawn(&self, task: Task<'static>);
+
+    /// True if the executor runs all tasks one after the other: work that only pays off
+    /// if tasks run in parallel, like parallel verification, is then skipped.
+    fn is_sequential(&self) -> bool {
+        false
+    }
+}
+
+/// The default executor, if the `rayon` feature is disabled: runs all tasks on the current thread.
+#[cfg(not(feature = "rayon"))]
+struct SequentialExecutor;
+
+#[cfg(not(feature = "rayon"))]
+impl Executor for SequentialExecutor {
+    fn join(&self, a: Task<'_>, b: Task<'_>) {
+        a();
+        b();
+    }
+
+    fn for_each(&self, len: usize, op: &(dyn Fn(usize) + Sync)) {
+        (0..len).for_each(op)
+    }
+
+    fn scope<'scope>(&self, op: ScopeOp<'_, 'scope>) {
+        op(&|task| task())
+    }
+
+    fn spawn(&self, task: Task<'static>) {
+        task()
+    }
+
+    fn is_sequential(&self) -> bool {
+        true
+    }
+}
+
+/// An executor that runs tasks on a rayon thread pool.
+#[cfg(feature = "rayon")]
+#[derive(Default)]
+pub struct RayonExecutor {
+    /// The thread pool, if not the global one.
+    thread_pool: Option<rayon::ThreadPool>,
+}
+
+#[cfg(feature = "rayon")]
+impl RayonExecutor {
+    /// Runs tasks on the global rayon thread pool.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs tasks on `thread_pool`.
+    pub fn with_thread_pool(thread_pool: rayon::ThreadPool) -> Self {
+        Self {
+            thread_pool: Some(thread_pool),
+        }
+    }
+
+    fn install<R: Send>(&self, op: impl FnOnce() -> R + Send) -> R {
+        match &self.thread_pool {
+            Some(thread_pool) => thread_pool.install(op),
+            None => op(),
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl Executor for RayonExecutor {
+    fn join(&self, a: Task<'_>, b: Task<'_>) {
+        self.install(|| rayon::join(a, b));
+    }
+
+    fn for_each(&self, len: usize, op: &(dyn Fn(usize) + Sync)) {
+        use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+        self.install(|| (0..len).into_par_iter().for_each(op))
+    }
+
+    fn scope<'scope>(&self, op: ScopeOp<'_, 'scope>) {
+        let op = |scope: &rayon::Scope<'scope>| op(&|task| scope.spawn(move |_| task()));
+        match &self.thread_pool {
+            Some(thread_pool) => thread_pool.in_place_scope(op),
+            None => rayon::in_place_scope(op),
+        }
+    }
+
+    fn spawn(&self, task: Task<'static>) {
+        match &self.thread_pool {
+            Some(thread_pool) => thread_pool.spawn(task),
+            None => rayon::spawn(task),
+        }
+    }
+
+    fn is_sequential(&self) -> bool {
+        let num_threads = match &self.thread_pool {
+            Some(thread_pool) => thread_pool.current_num_threads(),
+            None => rayon::current_num_threads(),
+        };
+        num_threads == 1
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl std::fmt::Debug for RayonExecutor {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt.debug_struct("RayonExecutor")
+            .field(
+                "thread_pool",
+                &self
+                    .thread_pool
+                    .as_ref()
+                    .map(|pool| pool.current_num_threads()),
+            )
+            .finish()
+    }
+}
+
+/// The executor of a database that was not configured with one.
+pub(crate) fn default_executor() -> Box<dyn Executor> {
+    #[cfg(feature = "rayon")]
+    return Box::new(RayonExecutor::new());
+
+    #[cfg(not(feature = "rayon"))]
+    return Box::new(SequentialExecutor);
+}
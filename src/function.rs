@@ -9,7 +9,7 @@ use crate::{
     salsa_struct::SalsaStructInDb,
     zalsa::{IngredientIndex, MemoIngredientIndex, Zalsa},
     zalsa_local::QueryOrigin,
-    Cycle, Database, Id, Revision,
+    CycleContext, Database, Durability, Id, Revision, Runtime,
 };
 
 use self::delete::DeletedEntries;
@@ -20,6 +20,7 @@ mod accumulated;
 mod backdate;
 mod delete;
 mod diff_outputs;
+mod durability_info;
 mod execute;
 mod fetch;
 mod inputs;
@@ -49,6 +50,10 @@ pub trait Configuration: Any {
     /// (and, if so, how).
     const CYCLE_STRATEGY: CycleRecoveryStrategy;
 
+    /// If `Some`, each newly computed memo is asserted to have at least this durability
+    /// (set with `#[salsa::tracked(expect_durability = ...)]`, debug builds only).
+    const EXPECTED_DURABILITY: Option<Durability>;
+
     /// Invokes after a new result `new_value`` has been computed for which an older memoized
     /// value existed `old_value`. Returns true if the new value is equal to the older one
     /// and hence should be "backdated" (i.e., marked as having last changed in an older revision,
@@ -73,7 +78,7 @@ pub trait Configuration: Any {
     /// This invokes the recovery function given by the user.
     fn recover_from_cycle<'db>(
         db: &'db Self::DbView,
-        cycle: &Cycle,
+        cycle: &CycleContext,
         input: Self::Input<'db>,
     ) -> Self::Output<'db>;
 }
@@ -233,7 +238,7 @@ where
         true
     }
 
-    fn reset_for_new_revision(&mut self) {
+    fn reset_for_new_revision(&mut self, _runtime: &mut Runtime) {
         std::mem::take(&mut self.deleted_entries);
     }
 
@@ -1,4 +1,6 @@
-use crate::zalsa_local::QueryRevisions;
+use crate::{
+    key::DatabaseKeyIndex, zalsa_local::QueryRevisions, Database, EventKind, EventKindMask,
+};
 
 use super::{memo::Memo, Configuration, IngredientImpl};
 
@@ -11,6 +13,8 @@ where
     /// on an old memo when a new memo has been produced to check whether there have been changed.
     pub(super) fn backdate_if_appropriate(
         &self,
+        db: &dyn Database,
+        database_key_index: DatabaseKeyIndex,
         old_memo: &Memo<C::Output<'_>>,
         revisions: &mut QueryRevisions,
         value: &C::Output<'_>,
@@ -30,6 +34,14 @@ where
 
                 assert!(old_memo.revisions.changed_at <= revisions.changed_at);
                 revisions.changed_at = old_memo.revisions.changed_at;
+
+                db.zalsa()
+                    .report_event(db, EventKindMask::DID_BACKDATE_VALUE, || {
+                        EventKind::DidBackdateValue {
+                            database_key: database_key_index,
+                            changed_at: revisions.changed_at,
+                        }
+                    });
             }
         }
     }
@@ -1,7 +1,7 @@
 use super::{memo::Memo, Configuration, IngredientImpl};
 use crate::{
     hash::FxHashSet, key::DependencyIndex, zalsa_local::QueryRevisions, AsDynDatabase as _,
-    DatabaseKeyIndex, Event, EventKind,
+    DatabaseKeyIndex, EventKind, EventKindMask,
 };
 
 impl<C> IngredientImpl<C>
@@ -48,13 +48,13 @@ where
     fn report_stale_output(db: &C::DbView, key: DatabaseKeyIndex, output: DependencyIndex) {
         let db = db.as_dyn_database();
 
-        db.salsa_event(&|| Event {
-            thread_id: std::thread::current().id(),
-            kind: EventKind::WillDiscardStaleOutput {
-                execute_key: key,
-                output_key: output,
-            },
-        });
+        db.zalsa()
+            .report_event(db, EventKindMask::WILL_DISCARD_STALE_OUTPUT, || {
+                EventKind::WillDiscardStaleOutput {
+                    execute_key: key,
+                    output_key: output,
+                }
+            });
 
         output.remove_stale_output(db, key);
     }
@@ -0,0 +1,18 @@
+use crate::{zalsa::ZalsaDatabase, DurabilityInfo, Id};
+
+use super::{Configuration, IngredientImpl};
+
+impl<C> IngredientImpl<C>
+where
+    C: Configuration,
+{
+    /// Returns the durability of the memo for `key` and the input that lowered it,
+    /// or `None` if there is no memo. Does not record any dependency.
+    pub fn durability_info(&self, db: &C::DbView, key: Id) -> Option<DurabilityInfo> {
+        let memo = self.get_memo_from_table_for(db.zalsa(), key)?;
+        Some(DurabilityInfo {
+            durability: memo.revisions.durability,
+            lowered_by: memo.revisions.durability_lowered_by.get(),
+        })
+    }
+}
@@ -1,7 +1,8 @@
 use std::sync::Arc;
 
 use crate::{
-    zalsa::ZalsaDatabase, zalsa_local::ActiveQueryGuard, Cycle, Database, Event, EventKind,
+    zalsa::ZalsaDatabase, zalsa_local::ActiveQueryGuard, AsDynDatabase as _, Cycle, CycleContext,
+    EventKind, EventKindMask,
 };
 
 use super::{memo::Memo, Configuration, IngredientImpl};
@@ -31,11 +32,10 @@ where
 
         tracing::info!("{:?}: executing query", database_key_index);
 
-        db.salsa_event(&|| Event {
-            thread_id: std::thread::current().id(),
-            kind: EventKind::WillExecute {
+        zalsa.report_event(db.as_dyn_database(), EventKindMask::WILL_EXECUTE, || {
+            EventKind::WillExecute {
                 database_key: database_key_index,
-            },
+            }
         });
 
         // If we already executed this query once, then use the tracked-struct ids from the
@@ -56,17 +56,21 @@ where
                     C::CYCLE_STRATEGY
                 );
                 match C::CYCLE_STRATEGY {
-                    crate::cycle::CycleRecoveryStrategy::Panic => cycle.throw(),
+                    crate::cycle::CycleRecoveryStrategy::Panic => {
+                        db.zalsa_local().throw_cycle(cycle)
+                    }
                     crate::cycle::CycleRecoveryStrategy::Fallback => {
                         if let Some(c) = active_query.take_cycle() {
                             assert!(c.is(&cycle));
-                            C::recover_from_cycle(db, &cycle, C::id_to_input(db, id))
+                            let context =
+                                CycleContext::new(db.as_dyn_database(), cycle, database_key_index);
+                            C::recover_from_cycle(db, &context, C::id_to_input(db, id))
                         } else {
                             // we are not a participant in this cycle
                             debug_assert!(!cycle
                                 .participant_keys()
                                 .any(|k| k == database_key_index));
-                            cycle.throw()
+                            db.zalsa_local().throw_cycle(cycle)
                         }
                     }
                 }
@@ -74,17 +78,36 @@ where
         };
         let mut revisions = active_query.pop();
 
+        if let Some(expected) = C::EXPECTED_DURABILITY {
+            if revisions.durability < expected {
+                panic!(
+                    "{database_key_index:?}: expected durability {expected:?}, \
+                    but computed durability {:?} (lowered by {:?})",
+                    revisions.durability,
+                    revisions.durability_lowered_by.get(),
+                );
+            }
+        }
+
         // If the new value is equal to the old one, then it didn't
         // really change, even if some of its inputs have. So we can
         // "backdate" its `changed_at` revision to be the same as the
         // old value.
         if let Some(old_memo) = &opt_old_memo {
-            self.backdate_if_appropriate(old_memo, &mut revisions, &value);
+            self.backdate_if_appropriate(
+                db.as_dyn_database(),
+                database_key_index,
+                old_memo,
+                &mut revisions,
+                &value,
+            );
             self.diff_outputs(db, database_key_index, old_memo, &mut revisions);
         }
 
         tracing::debug!("{database_key_index:?}: read_upgrade: result.revisions = {revisions:#?}");
 
-        self.insert_memo(zalsa, id, Memo::new(Some(value), revision_now, revisions))
+        let memo = self.insert_memo(zalsa, id, Memo::new(Some(value), revision_now, revisions));
+        memo.report_stored(db.as_dyn_database(), database_key_index);
+        memo
     }
 }
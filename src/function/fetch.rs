@@ -1,4 +1,7 @@
-use crate::{runtime::StampedValue, zalsa::ZalsaDatabase, AsDynDatabase as _, Id};
+use crate::{
+    runtime::StampedValue, stack_overflow::continue_on_new_thread, zalsa::ZalsaDatabase,
+    AsDynDatabase as _, Id, Revision,
+};
 
 use super::{memo::Memo, Configuration, IngredientImpl};
 
@@ -8,9 +11,15 @@ where
 {
     pub fn fetch<'db>(&'db self, db: &'db C::DbView, id: Id) -> &'db C::Output<'db> {
         let (zalsa, zalsa_local) = db.zalsas();
-        zalsa_local.unwind_if_revision_cancelled(db.as_dyn_database());
-
-        let memo = self.refresh_memo(db, id);
+        let memo = match zalsa.frozen_at() {
+            Some(frozen_at) => self
+                .fetch_frozen(db, id, frozen_at)
+                .unwrap_or_else(|| self.refresh_memo(db, id)),
+            None => {
+                zalsa_local.unwind_if_unfrozen_revision_cancelled(db.as_dyn_database());
+                self.refresh_memo(db, id)
+            }
+        };
         let StampedValue {
             value,
             durability,
@@ -18,7 +27,7 @@ where
         } = memo.revisions.stamped_value(memo.value.as_ref().unwrap());
 
         if let Some(evicted) = self.lru.record_use(id) {
-            self.evict_value_from_memo_for(zalsa, evicted);
+            self.evict_value_from_memo_for(db.as_dyn_database(), evicted);
         }
 
         zalsa_local.report_tracked_read(self.database_key_index(id).into(), durability, changed_at);
@@ -39,6 +48,32 @@ where
         }
     }
 
+    /// The fast path of a database frozen in revision `frozen_at` (see [`Database::freeze`]):
+    /// no new revision can start, so a memo verified in `frozen_at` is returned without
+    /// any verification. Other memos are verified once, by [`Self::refresh_memo`]: the
+    /// durability check of `shallow_verify_memo` may suffice, but a memo last verified
+    /// before the database was frozen needs a deep walk if inputs changed in between.
+    ///
+    /// [`Database::freeze`]: `crate::Database::freeze`
+    #[inline]
+    fn fetch_frozen<'db>(
+        &'db self,
+        db: &'db C::DbView,
+        id: Id,
+        frozen_at: Revision,
+    ) -> Option<&'db Memo<C::Output<'db>>> {
+        let memo_guard = self.get_memo_from_table_for(db.zalsa(), id);
+        if let Some(memo) = &memo_guard {
+            if memo.value.is_some() && memo.verified_at.load() == frozen_at {
+                // Unsafety invariant: memo is present in memo_map
+                unsafe {
+                    return Some(self.extend_memo_lifetime(memo));
+                }
+            }
+        }
+        None
+    }
+
     #[inline]
     fn fetch_hot<'db>(&'db self, db: &'db C::DbView, id: Id) -> Option<&'db Memo<C::Output<'db>>> {
         let zalsa = db.zalsa();
@@ -60,6 +95,17 @@ where
         let (zalsa, zalsa_local) = db.zalsas();
         let database_key_index = self.database_key_index(id);
 
+        // If the query stack is too deep for this thread, continue on a fresh one.
+        if let Some(stack_size) = zalsa.overflow_stack_size() {
+            if zalsa_local.at_query_depth_limit(zalsa.max_query_depth()) {
+                continue_on_new_thread(db.as_dyn_database(), stack_size, |db| {
+                    self.refresh_memo(db.as_view::<C::DbView>(), id);
+                });
+                // Look the memo up again, as the one returned on the new thread borrows the fork.
+                return self.fetch_hot(db, id);
+            }
+        }
+
         // Try to claim this query: if someone else has claimed it already, go back and start again.
         let _claim_guard = zalsa.sync_table_for(id).claim(
             db.as_dyn_database(),
@@ -69,7 +115,11 @@ where
         )?;
 
         // Push the query on the stack.
-        let active_query = zalsa_local.push_query(database_key_index);
+        let active_query = zalsa_local.push_query(
+            database_key_index,
+            zalsa.max_query_depth(),
+            zalsa.max_durability(),
+        );
 
         // Now that we've claimed the item, check again to see if there's a "hot" value.
         let zalsa = db.zalsa();
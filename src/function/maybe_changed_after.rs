@@ -1,7 +1,9 @@
 use crate::{
-    key::DatabaseKeyIndex,
+    key::{DatabaseKeyIndex, DependencyIndex},
+    parallel::{prefetch, PrefetchWalk},
+    stack_overflow::continue_on_new_thread,
     zalsa::{Zalsa, ZalsaDatabase},
-    zalsa_local::{ActiveQueryGuard, EdgeKind, QueryOrigin},
+    zalsa_local::{ActiveQueryGuard, EdgeKind, QueryEdges, QueryOrigin},
     AsDynDatabase as _, Id, Revision,
 };
 
@@ -18,7 +20,10 @@ where
         revision: Revision,
     ) -> bool {
         let (zalsa, zalsa_local) = db.zalsas();
-        zalsa_local.unwind_if_revision_cancelled(db.as_dyn_database());
+        let frozen_at = zalsa.frozen_at();
+        if frozen_at.is_none() {
+            zalsa_local.unwind_if_unfrozen_revision_cancelled(db.as_dyn_database());
+        }
 
         loop {
             let database_key_index = self.database_key_index(id);
@@ -26,9 +31,13 @@ where
             tracing::debug!("{database_key_index:?}: maybe_changed_after(revision = {revision:?})");
 
             // Check if we have a verified version: this is the hot path.
+            // In a frozen database, a memo verified since freezing needs no check at all
+            // (see `fetch_frozen`), so the deep walks of its dependents stop here.
             let memo_guard = self.get_memo_from_table_for(zalsa, id);
             if let Some(memo) = &memo_guard {
-                if self.shallow_verify_memo(db, zalsa, database_key_index, memo) {
+                if frozen_at == Some(memo.verified_at.load())
+                    || self.shallow_verify_memo(db, zalsa, database_key_index, memo)
+                {
                     return memo.revisions.changed_at > revision;
                 }
                 drop(memo_guard); // release the arc-swap guard before cold path
@@ -53,13 +62,26 @@ where
         let (zalsa, zalsa_local) = db.zalsas();
         let database_key_index = self.database_key_index(key_index);
 
+        // If the query stack is too deep for this thread, continue on a fresh one.
+        if let Some(stack_size) = zalsa.overflow_stack_size() {
+            if zalsa_local.at_query_depth_limit(zalsa.max_query_depth()) {
+                return continue_on_new_thread(db.as_dyn_database(), stack_size, |db| {
+                    self.maybe_changed_after_cold(db.as_view::<C::DbView>(), key_index, revision)
+                });
+            }
+        }
+
         let _claim_guard = zalsa.sync_table_for(key_index).claim(
             db.as_dyn_database(),
             zalsa_local,
             database_key_index,
             self.memo_ingredient_index,
         )?;
-        let active_query = zalsa_local.push_query(database_key_index);
+        let active_query = zalsa_local.push_query(
+            database_key_index,
+            zalsa.max_query_depth(),
+            zalsa.max_durability(),
+        );
 
         // Load the current memo, if any.
         let Some(old_memo) = self.get_memo_from_table_for(zalsa, key_index) else {
@@ -81,7 +103,7 @@ where
         // It is possible the result will be equal to the old value and hence
         // backdated. In that case, although we will have computed a new memo,
         // the value has not logically changed.
-        if old_memo.value.is_some() {
+        if old_memo.value.is_some() && !zalsa_local.is_speculative() {
             let memo = self.execute(db, active_query, Some(old_memo));
             let changed_at = memo.revisions.changed_at;
             return Some(changed_at > revision);
@@ -91,6 +113,30 @@ where
         Some(true)
     }
 
+    /// Speculatively verifies the inputs of a memo in parallel, so that the sequential walk
+    /// in [`Self::deep_verify_memo`] finds them verified already
+    /// (see [`Storage::with_parallel_verification`](`crate::Storage::with_parallel_verification`)).
+    ///
+    /// Only the inputs that were read before the first output are verified: later inputs
+    /// may read an output, which is only validated when the sequential walk reaches it.
+    fn prefetch_inputs(
+        &self,
+        db: &C::DbView,
+        edges: &QueryEdges,
+        last_verified_at: Revision,
+        min_inputs: usize,
+    ) {
+        let inputs: Vec<DependencyIndex> = edges
+            .input_outputs
+            .iter()
+            .take_while(|(edge_kind, _)| *edge_kind == EdgeKind::Input)
+            .map(|&(_, dependency_index)| dependency_index)
+            .collect();
+        if inputs.len() >= min_inputs {
+            prefetch(db.as_dyn_database(), &inputs, last_verified_at);
+        }
+    }
+
     /// True if the memo's value and `changed_at` time is still valid in this revision.
     /// Does only a shallow O(1) check, doesn't walk the dependencies.
     #[inline]
@@ -182,6 +228,17 @@ where
                 // valid, then some later input I1 might never have executed at all, so verifying
                 // it is still up to date is meaningless.
                 let last_verified_at = old_memo.verified_at.load();
+                let _walk = match zalsa.parallel_verification() {
+                    Some(min_inputs)
+                        if !db.zalsa_local().is_speculative()
+                            && !zalsa.executor().is_sequential() =>
+                    {
+                        let walk = PrefetchWalk::start(db.as_dyn_database());
+                        self.prefetch_inputs(db, edges, last_verified_at, min_inputs);
+                        Some(walk)
+                    }
+                    _ => None,
+                };
                 for &(edge_kind, dependency_index) in edges.input_outputs.iter() {
                     match edge_kind {
                         EdgeKind::Input => {
@@ -7,7 +7,7 @@ use crossbeam::atomic::AtomicCell;
 
 use crate::zalsa_local::QueryOrigin;
 use crate::{
-    key::DatabaseKeyIndex, zalsa::Zalsa, zalsa_local::QueryRevisions, Event, EventKind, Id,
+    key::DatabaseKeyIndex, zalsa::Zalsa, zalsa_local::QueryRevisions, EventKind, EventKindMask, Id,
     Revision,
 };
 
@@ -60,7 +60,8 @@ impl<C: Configuration> IngredientImpl<C> {
     /// Evicts the existing memo for the given key, replacing it
     /// with an equivalent memo that has no value. If the memo is untracked, BaseInput,
     /// or has values assigned as output of another query, this has no effect.
-    pub(super) fn evict_value_from_memo_for<'db>(&'db self, zalsa: &'db Zalsa, id: Id) {
+    pub(super) fn evict_value_from_memo_for(&self, db: &dyn crate::Database, id: Id) {
+        let zalsa = db.zalsa();
         let Some(memo) = self.get_memo_from_table_for(zalsa, id) else {
             return;
         };
@@ -83,6 +84,12 @@ impl<C: Configuration> IngredientImpl<C> {
                 ));
 
                 self.insert_memo_into_table_for(zalsa, id, memo_evicted);
+
+                zalsa.report_event(db, EventKindMask::DID_EVICT_VALUE, || {
+                    EventKind::DidEvictValue {
+                        database_key: self.database_key_index(id),
+                    }
+                });
             }
         }
     }
@@ -130,16 +137,32 @@ impl<V> Memo<V> {
         revision_now: Revision,
         database_key_index: DatabaseKeyIndex,
     ) {
-        db.salsa_event(&|| Event {
-            thread_id: std::thread::current().id(),
-            kind: EventKind::DidValidateMemoizedValue {
-                database_key: database_key_index,
-            },
-        });
+        db.zalsa()
+            .report_event(db, EventKindMask::DID_VALIDATE_MEMOIZED_VALUE, || {
+                EventKind::DidValidateMemoizedValue {
+                    database_key: database_key_index,
+                }
+            });
 
         self.verified_at.store(revision_now);
     }
 
+    /// Reports a `DidStoreMemo` event for this memo, which was just stored.
+    pub(super) fn report_stored(
+        &self,
+        db: &dyn crate::Database,
+        database_key_index: DatabaseKeyIndex,
+    ) {
+        db.zalsa()
+            .report_event(db, EventKindMask::DID_STORE_MEMO, || {
+                EventKind::DidStoreMemo {
+                    database_key: database_key_index,
+                    changed_at: self.revisions.changed_at,
+                    durability: self.revisions.durability,
+                }
+            });
+    }
+
     pub(super) fn mark_outputs_as_verified(
         &self,
         db: &dyn crate::Database,
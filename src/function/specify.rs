@@ -1,6 +1,7 @@
 use crossbeam::atomic::AtomicCell;
 
 use crate::{
+    durability::LoweredBy,
     tracked_struct::TrackedStructInDb,
     zalsa::ZalsaDatabase,
     zalsa_local::{QueryOrigin, QueryRevisions},
@@ -67,13 +68,20 @@ where
         let mut revisions = QueryRevisions {
             changed_at: current_deps.changed_at,
             durability: current_deps.durability,
+            durability_lowered_by: LoweredBy::new(Some(active_query_key.into())),
             origin: QueryOrigin::Assigned(active_query_key),
             tracked_struct_ids: Default::default(),
             accumulated: Default::default(),
         };
 
         if let Some(old_memo) = self.get_memo_from_table_for(zalsa, key) {
-            self.backdate_if_appropriate(&old_memo, &mut revisions, &value);
+            self.backdate_if_appropriate(
+                db.as_dyn_database(),
+                self.database_key_index(key),
+                &old_memo,
+                &mut revisions,
+                &value,
+            );
             self.diff_outputs(db, database_key_index, &old_memo, &mut revisions);
         }
 
@@ -88,10 +96,11 @@ where
             memo.tracing_debug(),
             key
         );
-        self.insert_memo(zalsa, key, memo);
+        let database_key_index = self.database_key_index(key);
+        self.insert_memo(zalsa, key, memo)
+            .report_stored(db.as_dyn_database(), database_key_index);
 
         // Record that the current query *specified* a value for this cell.
-        let database_key_index = self.database_key_index(key);
         zalsa_local.add_output(database_key_index.into());
     }
 
@@ -8,7 +8,7 @@ use crate::{
     cycle::CycleRecoveryStrategy,
     zalsa::{IngredientIndex, MemoIngredientIndex},
     zalsa_local::QueryOrigin,
-    Database, DatabaseKeyIndex, Id,
+    Database, DatabaseKeyIndex, Id, Runtime,
 };
 
 use super::Revision;
@@ -92,11 +92,11 @@ pub trait Ingredient: Any + std::fmt::Debug + Send + Sync {
     /// Many ingredients, given an `&'db`-reference to the database,
     /// use unsafe code to return `&'db`-references to internal values.
     /// The backing memory for those values can only be freed once an `&mut`-reference to the
-    /// database is created.
+    /// database is created. `runtime` gives access to the table, e.g. to reset slots.
     ///
     /// **Important:** to actually receive resets, the ingredient must set
     /// [`IngredientRequiresReset::RESET_ON_NEW_REVISION`] to true.
-    fn reset_for_new_revision(&mut self);
+    fn reset_for_new_revision(&mut self, runtime: &mut Runtime);
 
     fn fmt_index(&self, index: Option<crate::Id>, fmt: &mut fmt::Formatter<'_>) -> fmt::Result;
 }
@@ -1,9 +1,15 @@
-use std::{any::Any, fmt, ops::DerefMut};
+use std::{
+    any::Any,
+    cell::UnsafeCell,
+    fmt,
+    ops::DerefMut,
+    sync::atomic::{AtomicBool, Ordering},
+};
 
 pub mod input_field;
 pub mod setter;
 
-use crossbeam::atomic::AtomicCell;
+use crossbeam::{atomic::AtomicCell, queue::SegQueue};
 use input_field::FieldIngredientImpl;
 use parking_lot::Mutex;
 
@@ -16,7 +22,7 @@ use crate::{
     table::{memo::MemoTable, sync::SyncTable, Slot, Table},
     zalsa::{IngredientIndex, Zalsa},
     zalsa_local::QueryOrigin,
-    Database, Durability, Id, Revision, Runtime,
+    Database, Durability, EventKind, EventKindMask, Id, Revision, Runtime,
 };
 
 pub trait Configuration: Any {
@@ -66,6 +72,14 @@ pub struct IngredientImpl<C: Configuration> {
     ingredient_index: IngredientIndex,
     singleton_index: AtomicCell<Option<Id>>,
     singleton_lock: Mutex<()>,
+
+    /// Ids of deleted inputs, available for re-use
+    free_list: SegQueue<Id>,
+
+    /// Ids of inputs deleted in the current revision: stale ids may still be used
+    /// in this revision, so they only become available for re-use in the next one.
+    deleted: Vec<Id>,
+
     _phantom: std::marker::PhantomData<C::Struct>,
 }
 
@@ -75,6 +89,8 @@ impl<C: Configuration> IngredientImpl<C> {
             ingredient_index: index,
             singleton_index: AtomicCell::new(None),
             singleton_lock: Default::default(),
+            free_list: Default::default(),
+            deleted: Default::default(),
             _phantom: std::marker::PhantomData,
         }
     }
@@ -108,12 +124,12 @@ impl<C: Configuration> IngredientImpl<C> {
             None
         };
 
-        let id = zalsa_local.allocate(zalsa.table(), self.ingredient_index, || Value::<C> {
-            fields,
-            stamps,
-            memos: Default::default(),
-            syncs: Default::default(),
-        });
+        let id = match self.free_list.pop() {
+            Some(id) => self.reuse(zalsa, id, fields, stamps),
+            None => zalsa_local.allocate(zalsa.table(), self.ingredient_index, || {
+                Value::<C>::new(fields, stamps)
+            }),
+        };
 
         if C::IS_SINGLETON {
             self.singleton_index.store(Some(id));
@@ -123,6 +139,128 @@ impl<C: Configuration> IngredientImpl<C> {
         FromId::from_id(id)
     }
 
+    /// Creates one new input for each `(fields, stamps)` pair in `inputs`,
+    /// allocating their slots in bulk. Returns the new inputs in order.
+    ///
+    /// # Panics
+    ///
+    /// If this input is declared as a singleton.
+    pub fn new_inputs(
+        &self,
+        db: &dyn Database,
+        inputs: impl IntoIterator<Item = (C::Fields, C::Stamps)>,
+    ) -> Vec<C::Struct> {
+        assert!(
+            !C::IS_SINGLETON,
+            "singleton structs may not be created in batches"
+        );
+
+        let (zalsa, zalsa_local) = db.zalsas();
+        let mut inputs = inputs.into_iter();
+
+        // Re-use the ids of deleted inputs first, then allocate the rest in bulk.
+        let mut ids = vec![];
+        while let Some(id) = self.free_list.pop() {
+            let Some((fields, stamps)) = inputs.next() else {
+                self.free_list.push(id);
+                break;
+            };
+            ids.push(self.reuse(zalsa, id, fields, stamps));
+        }
+        let values = inputs.map(|(fields, stamps)| Value::<C>::new(fields, stamps));
+        ids.extend(zalsa_local.allocate_batch(zalsa.table(), self.ingredient_index, values));
+
+        ids.into_iter().map(FromId::from_id).collect()
+    }
+
+    /// Stores `fields` and `stamps` in the slot of the deleted input `id`, taken from the free list.
+    ///
+    /// The memos and syncs of the slot are kept: stale copies of `id` may still be used
+    /// to call tracked functions (which cannot read the fields of the deleted input),
+    /// so there may be references into them.
+    fn reuse(&self, zalsa: &Zalsa, id: Id, fields: C::Fields, stamps: C::Stamps) -> Id {
+        let data_raw = Self::data_raw(zalsa.table(), id);
+
+        // SAFETY: `id` was deleted in an earlier revision and we took it from the free list,
+        // so no other thread writes to its slot. Stale copies of `id` may still create
+        // `&Value<C>` for the slot, but the fields and stamps are in `UnsafeCell`s, and while the
+        // slot is marked as deleted, there are no references to them (see `Value::fields`):
+        // readers observe the new ones once `deleted` is cleared.
+        unsafe {
+            let data = &*data_raw;
+            assert!(
+                data.is_deleted(),
+                "free list entry for `{id:?}` was not deleted"
+            );
+            *data.fields.get() = Some(fields);
+            *data.stamps.get() = stamps;
+            data.deleted.store(false, Ordering::Release);
+        }
+
+        id
+    }
+
+    /// Deletes the input `id`, dropping its field values. This is the first half of deleting an
+    /// input and must be followed by a call to [`discard_deleted_input`](`Self::discard_deleted_input`),
+    /// which discards the memos attached to `id`. The id becomes available for re-use
+    /// in the next revision.
+    ///
+    /// Every field is marked as changed in the current revision, so that queries which read
+    /// from the deleted input will be re-executed.
+    ///
+    /// # Panics
+    ///
+    /// If the input was already deleted.
+    pub fn delete_input(&mut self, runtime: &mut Runtime, id: C::Struct) {
+        let id: Id = id.as_id();
+        let r = Self::data_raw(runtime.table(), id);
+
+        // SAFETY: We hold `&mut` on the runtime so no `&`-references can be active.
+        // Also, we don't access any other data from the table while `r` is active.
+        let r = unsafe { &mut *r };
+
+        assert!(
+            r.fields.get_mut().is_some(),
+            "input `{id:?}` was already deleted"
+        );
+
+        for stamp in r.stamps.get_mut().iter_mut() {
+            if stamp.durability != Durability::LOW {
+                runtime.report_tracked_write(stamp.durability);
+            }
+            stamp.changed_at = runtime.current_revision();
+        }
+        *r.fields.get_mut() = None;
+        *r.deleted.get_mut() = true;
+        self.deleted.push(id);
+
+        if C::IS_SINGLETON {
+            self.singleton_index.store(None);
+        }
+    }
+
+    /// Discards all memos attached to the input `id`, which must have been deleted
+    /// with [`delete_input`](`Self::delete_input`).
+    pub fn discard_deleted_input(&self, db: &dyn Database, id: C::Struct) {
+        let id: Id = id.as_id();
+
+        db.zalsa()
+            .report_event(db, EventKindMask::DID_DISCARD, || EventKind::DidDiscard {
+                key: self.database_key_index(FromId::from_id(id)),
+            });
+
+        let zalsa = db.zalsa();
+        let data = Self::data_raw(zalsa.table(), id);
+
+        // SAFETY: The input was deleted with an `&mut` reference to the database,
+        // so there are no outstanding references into its memo table.
+        let memo_table = unsafe {
+            assert!((*data).is_deleted(), "input `{id:?}` was not deleted");
+            std::mem::take(&mut (*data).memos)
+        };
+        memo_table.discard(db, id);
+    }
+
     /// Change the value of the field `field_index` to a new value.
     ///
     /// # Parameters
@@ -147,7 +285,7 @@ impl<C: Configuration> IngredientImpl<C> {
         // Also, we don't access any other data from the table while `r` is active.
         let r = unsafe { &mut *r };
 
-        let stamp = &mut r.stamps[field_index];
+        let stamp = &mut r.stamps.get_mut()[field_index];
 
         if stamp.durability != Durability::LOW {
             runtime.report_tracked_write(stamp.durability);
@@ -155,7 +293,30 @@ impl<C: Configuration> IngredientImpl<C> {
 
         stamp.durability = durability.unwrap_or(stamp.durability);
         stamp.changed_at = runtime.current_revision();
-        setter(&mut r.fields)
+        setter(r.fields_mut())
+    }
+
+    /// Reports a `DidSetInputField` event for the field `field_index` of `id`,
+    /// which was just set with [`set_field`](`Self::set_field`).
+    /// Takes the index of this ingredient since the ingredient itself is borrowed
+    /// mutably while setting the field.
+    pub fn report_set_field(
+        db: &dyn Database,
+        ingredient_index: IngredientIndex,
+        id: C::Struct,
+        field_index: usize,
+    ) {
+        let zalsa = db.zalsa();
+        let id = id.as_id();
+        zalsa.report_event(db, EventKindMask::DID_SET_INPUT_FIELD, || {
+            EventKind::DidSetInputField {
+                key: DatabaseKeyIndex {
+                    ingredient_index: ingredient_index.successor(field_index),
+                    key_index: id,
+                },
+                durability: Self::data(zalsa, id).stamps()[field_index].durability,
+            }
+        });
     }
 
     /// Get the singleton input previously created (if any).
@@ -180,7 +341,8 @@ impl<C: Configuration> IngredientImpl<C> {
         let field_ingredient_index = self.ingredient_index.successor(field_index);
         let id = id.as_id();
         let value = Self::data(zalsa, id);
-        let stamp = &value.stamps[field_index];
+        let fields = value.fields();
+        let stamp = &value.stamps()[field_index];
         zalsa_local.report_tracked_read(
             DependencyIndex {
                 ingredient_index: field_ingredient_index,
@@ -189,7 +351,7 @@ impl<C: Configuration> IngredientImpl<C> {
             stamp.durability,
             stamp.changed_at,
         );
-        &value.fields
+        fields
     }
 
     /// Peek at the field values without recording any read dependency.
@@ -198,7 +360,7 @@ impl<C: Configuration> IngredientImpl<C> {
         let zalsa = db.zalsa();
         let id = id.as_id();
         let value = Self::data(zalsa, id);
-        &value.fields
+        value.fields()
     }
 }
 
@@ -251,11 +413,13 @@ impl<C: Configuration> Ingredient for IngredientImpl<C> {
     }
 
     fn requires_reset_for_new_revision(&self) -> bool {
-        false
+        true
     }
 
-    fn reset_for_new_revision(&mut self) {
-        panic!("unexpected call to `reset_for_new_revision`")
+    fn reset_for_new_revision(&mut self, _runtime: &mut Runtime) {
+        for id in self.deleted.drain(..) {
+            self.free_list.push(id);
+        }
     }
 
     fn fmt_index(&self, index: Option<Id>, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -290,18 +454,80 @@ where
 {
     /// Fields of this input struct. They can change across revisions,
     /// but they do not change within a particular revision.
-    fields: C::Fields,
+    /// `None` if the input has been deleted.
+    ///
+    /// In an `UnsafeCell`, as the new input that re-uses the slot of a deleted one
+    /// writes it while stale ids may still be used to access the slot.
+    fields: UnsafeCell<Option<C::Fields>>,
 
     /// The revision and durability information for each field: when did this field last change.
-    stamps: C::Stamps,
+    /// In an `UnsafeCell`, like `fields`.
+    stamps: UnsafeCell<C::Stamps>,
 
     /// Memos
     memos: MemoTable,
 
     /// Syncs
     syncs: SyncTable,
+
+    /// True if the input has been deleted and its slot was not re-used yet.
+    /// The fields and stamps of a deleted input are only accessed with `&mut` access
+    /// to the database, or by the new input that re-uses the slot (see `IngredientImpl::reuse`).
+    deleted: AtomicBool,
+}
+
+impl<C> Value<C>
+where
+    C: Configuration,
+{
+    fn new(fields: C::Fields, stamps: C::Stamps) -> Self {
+        Self {
+            fields: UnsafeCell::new(Some(fields)),
+            stamps: UnsafeCell::new(stamps),
+            memos: Default::default(),
+            syncs: Default::default(),
+            deleted: AtomicBool::new(false),
+        }
+    }
+
+    fn is_deleted(&self) -> bool {
+        self.deleted.load(Ordering::Acquire)
+    }
+
+    #[track_caller]
+    fn fields(&self) -> &C::Fields {
+        assert!(
+            !self.is_deleted(),
+            "access to the fields of a deleted input"
+        );
+        // SAFETY: The input is not deleted, so the fields are only written with `&mut` access
+        // to the database (and `is_deleted` synchronized with their write by `reuse`).
+        unsafe { (*self.fields.get()).as_ref().unwrap() }
+    }
+
+    #[track_caller]
+    fn stamps(&self) -> &C::Stamps {
+        assert!(
+            !self.is_deleted(),
+            "access to the fields of a deleted input"
+        );
+        // SAFETY: See `fields`.
+        unsafe { &*self.stamps.get() }
+    }
+
+    #[track_caller]
+    fn fields_mut(&mut self) -> &mut C::Fields {
+        self.fields
+            .get_mut()
+            .as_mut()
+            .expect("access to the fields of a deleted input")
+    }
 }
 
+// SAFETY: The fields and stamps in `UnsafeCell`s are `Sync`, and they are only written
+// with `&mut` access or while no references to them exist (see `IngredientImpl::reuse`).
+unsafe impl<C: Configuration> Sync for Value<C> {}
+
 pub trait HasBuilder {
     type Builder;
 }
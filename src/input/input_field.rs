@@ -3,7 +3,7 @@ use crate::ingredient::{fmt_index, Ingredient};
 use crate::input::Configuration;
 use crate::zalsa::IngredientIndex;
 use crate::zalsa_local::QueryOrigin;
-use crate::{Database, DatabaseKeyIndex, Id, Revision};
+use crate::{Database, DatabaseKeyIndex, Id, Revision, Runtime};
 use std::fmt;
 use std::marker::PhantomData;
 
@@ -58,7 +58,9 @@ where
         let zalsa = db.zalsa();
         let input = input.unwrap();
         let value = <IngredientImpl<C>>::data(zalsa, input);
-        value.stamps[self.field_index].changed_at > revision
+        // The stamps of a deleted input may be overwritten when its slot is re-used.
+        // Deleting an input changes its fields, so it has changed anyway.
+        value.is_deleted() || value.stamps()[self.field_index].changed_at > revision
     }
 
     fn origin(&self, _db: &dyn Database, _key_index: Id) -> Option<QueryOrigin> {
@@ -85,7 +87,7 @@ where
         false
     }
 
-    fn reset_for_new_revision(&mut self) {
+    fn reset_for_new_revision(&mut self, _runtime: &mut Runtime) {
         panic!("unexpected call: input fields don't register for resets");
     }
 
@@ -1,22 +1,33 @@
 use std::marker::PhantomData;
 
+use crate::id::AsId;
 use crate::input::{Configuration, IngredientImpl};
-use crate::{Durability, Runtime};
+use crate::{Database, Durability, Runtime};
 
 /// Setter for a field of an input.
 pub trait Setter: Sized {
     type FieldTy;
     fn with_durability(self, durability: Durability) -> Self;
     fn to(self, value: Self::FieldTy) -> Self::FieldTy;
+
+    /// Like [`to`](`Self::to`), but only sets the field if `value` differs from the current value
+    /// (or if a different durability was requested via [`with_durability`](`Self::with_durability`)).
+    ///
+    /// If nothing would change, no new revision is started and the dependents of this field
+    /// do not have to be re-validated. Returns the old value if the field was set and `None` otherwise.
+    fn to_if_changed(self, value: Self::FieldTy) -> Option<Self::FieldTy>
+    where
+        Self::FieldTy: Eq;
 }
 
 #[must_use]
 pub struct SetterImpl<'setter, C: Configuration, S, F> {
-    runtime: &'setter mut Runtime,
+    db: &'setter mut dyn Database,
     id: C::Struct,
-    ingredient: &'setter mut IngredientImpl<C>,
+    ingredient: fn(&mut dyn Database) -> (&mut IngredientImpl<C>, &mut Runtime),
     durability: Option<Durability>,
     field_index: usize,
+    getter: fn(&C::Fields) -> &F,
     setter: S,
     phantom: PhantomData<fn(F)>,
 }
@@ -27,18 +38,20 @@ where
     S: FnOnce(&mut C::Fields, F) -> F,
 {
     pub fn new(
-        runtime: &'setter mut Runtime,
+        db: &'setter mut dyn Database,
         id: C::Struct,
         field_index: usize,
-        ingredient: &'setter mut IngredientImpl<C>,
+        ingredient: fn(&mut dyn Database) -> (&mut IngredientImpl<C>, &mut Runtime),
+        getter: fn(&C::Fields) -> &F,
         setter: S,
     ) -> Self {
         SetterImpl {
-            runtime,
+            db,
             id,
             field_index,
             ingredient,
             durability: None,
+            getter,
             setter,
             phantom: PhantomData,
         }
@@ -59,17 +72,40 @@ where
 
     fn to(self, value: F) -> F {
         let Self {
-            runtime,
+            db,
             id,
             ingredient,
             durability,
             field_index,
+            getter: _,
             setter,
             phantom: _,
         } = self;
 
-        ingredient.set_field(runtime, id, field_index, durability, |tuple| {
+        // Acquiring the ingredient mutably starts a new revision.
+        let (ingredient, runtime) = ingredient(db);
+        let ingredient_index = ingredient.ingredient_index;
+        let old_value = ingredient.set_field(runtime, id, field_index, durability, |tuple| {
             setter(tuple, value)
-        })
+        });
+        IngredientImpl::<C>::report_set_field(db, ingredient_index, id, field_index);
+        old_value
+    }
+
+    fn to_if_changed(self, value: F) -> Option<F>
+    where
+        F: Eq,
+    {
+        // Peek at the current value without starting a new revision.
+        let data = IngredientImpl::<C>::data(self.db.zalsa(), self.id.as_id());
+        let old_durability = data.stamps()[self.field_index].durability;
+        let unchanged = *(self.getter)(data.fields()) == value
+            && self.durability.unwrap_or(old_durability) == old_durability;
+
+        if unchanged {
+            None
+        } else {
+            Some(self.to(value))
+        }
     }
 }
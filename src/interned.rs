@@ -1,4 +1,3 @@
-use crate::durability::Durability;
 use crate::id::AsId;
 use crate::ingredient::fmt_index;
 use crate::key::DependencyIndex;
@@ -8,7 +7,7 @@ use crate::table::sync::SyncTable;
 use crate::table::Slot;
 use crate::zalsa::IngredientIndex;
 use crate::zalsa_local::QueryOrigin;
-use crate::{Database, DatabaseKeyIndex, Id};
+use crate::{Database, DatabaseKeyIndex, EventKind, EventKindMask, Id, Runtime};
 use std::fmt;
 use std::hash::{BuildHasher, Hash, Hasher};
 use std::marker::PhantomData;
@@ -128,10 +127,10 @@ where
         db: &'db dyn crate::Database,
         data: impl Lookup<C::Data<'db>>,
     ) -> C::Struct<'db> {
-        let zalsa_local = db.zalsa_local();
+        let (zalsa, zalsa_local) = db.zalsas();
         zalsa_local.report_tracked_read(
             DependencyIndex::for_table(self.ingredient_index),
-            Durability::MAX,
+            zalsa.max_durability(),
             self.reset_at,
         );
 
@@ -179,6 +178,16 @@ where
                     syncs: Default::default(),
                 });
                 entry.insert(next_id);
+
+                zalsa.report_event(db, EventKindMask::DID_INTERN_VALUE, || {
+                    EventKind::DidInternValue {
+                        key: DatabaseKeyIndex {
+                            ingredient_index: self.ingredient_index,
+                            key_index: next_id,
+                        },
+                    }
+                });
+
                 C::struct_from_id(next_id)
             }
         }
@@ -258,7 +267,7 @@ where
         false
     }
 
-    fn reset_for_new_revision(&mut self) {
+    fn reset_for_new_revision(&mut self, _runtime: &mut Runtime) {
         // Interned ingredients do not, normally, get deleted except when they are "reset" en masse.
         // There ARE methods (e.g., `clear_deleted_entries` and `remove`) for deleting individual
         // items, but those are only used for tracked struct ingredients.
@@ -8,6 +8,9 @@ mod database;
 mod database_impl;
 mod durability;
 mod event;
+#[cfg(feature = "event-log")]
+mod event_log;
+mod executor;
 mod function;
 mod hash;
 mod id;
@@ -17,11 +20,15 @@ mod interned;
 mod key;
 mod nonce;
 mod par_map;
+mod parallel;
+mod query_stack;
 mod revision;
 mod runtime;
 mod salsa_struct;
+mod stack_overflow;
 mod storage;
 mod table;
+mod tracked_collection;
 mod tracked_struct;
 mod update;
 mod views;
@@ -31,22 +38,42 @@ mod zalsa_local;
 pub use self::accumulator::Accumulator;
 pub use self::cancelled::Cancelled;
 pub use self::cycle::Cycle;
+pub use self::cycle::CycleContext;
+pub use self::cycle::CycleEdge;
 pub use self::database::AsDynDatabase;
 pub use self::database::Database;
 pub use self::database_impl::DatabaseImpl;
 pub use self::durability::Durability;
+pub use self::durability::DurabilityInfo;
 pub use self::event::Event;
 pub use self::event::EventKind;
+pub use self::event::EventKindMask;
+#[cfg(feature = "event-log")]
+pub use self::event_log::{EventRecorder, RecordedEvent, RecordedEventKind, RecordedKey};
 pub use self::id::Id;
 pub use self::input::setter::Setter;
 pub use self::key::DatabaseKeyIndex;
+pub use self::key::DependencyIndex;
+pub use self::query_stack::QueryStack;
 pub use self::revision::Revision;
+pub use self::runtime::BlockedThread;
 pub use self::runtime::Runtime;
+pub use self::runtime::WaitForGraph;
+pub use self::stack_overflow::StackOverflow;
 pub use self::storage::Storage;
+pub use self::tracked_collection::TrackedMap;
+pub use self::tracked_collection::TrackedVec;
 pub use self::update::Update;
 pub use self::zalsa::IngredientIndex;
 pub use crate::attach::with_attached_database;
-pub use par_map::par_map;
+#[cfg(feature = "rayon")]
+pub use executor::RayonExecutor;
+pub use executor::{Executor, ScopeOp, Task};
+pub use par_map::{par_filter_map, par_for_each, par_map, par_map_with};
+pub use parallel::join;
+pub use parallel::scope;
+pub use parallel::spawn;
+pub use parallel::Scope;
 pub use salsa_macros::accumulator;
 pub use salsa_macros::db;
 pub use salsa_macros::input;
@@ -71,6 +98,7 @@ pub mod plumbing {
     pub use crate::attach::attach;
     pub use crate::attach::with_attached_database;
     pub use crate::cycle::Cycle;
+    pub use crate::cycle::CycleContext;
     pub use crate::cycle::CycleRecoveryStrategy;
     pub use crate::database::current_revision;
     pub use crate::database::Database;
@@ -102,6 +130,7 @@ pub mod plumbing {
     pub use crate::zalsa::ZalsaDatabase;
     pub use crate::zalsa_local::ZalsaLocal;
 
+    pub use salsa_macro_rules::cycle_result_recovery;
     pub use salsa_macro_rules::macro_if;
     pub use salsa_macro_rules::maybe_backdate;
     pub use salsa_macro_rules::maybe_clone;
@@ -1,54 +1,89 @@
-use std::ops::Deref;
+use parking_lot::Mutex;
 
-use rayon::iter::{FromParallelIterator, IntoParallelIterator, ParallelIterator};
+use crate::{parallel::Forks, Database};
 
-use crate::Database;
+/// Maps `op` over `inputs` in parallel with the executor of the database, see [`par_map_with`].
+pub fn par_map<Db, D, E, C>(db: &Db, inputs: impl IntoIterator<Item = D>, op: fn(&Db, D) -> E) -> C
+where
+    Db: Database + ?Sized,
+    D: Send,
+    E: Send,
+    C: FromIterator<E>,
+{
+    par_map_with(db, inputs, op)
+}
 
-pub fn par_map<Db, D, E, C>(
-    db: &Db,
-    inputs: impl IntoParallelIterator<Item = D>,
-    op: fn(&Db, D) -> E,
-) -> C
+/// Maps `op` over `inputs` in parallel with the executor of the database
+/// (see [`Storage::with_executor`](`crate::Storage::with_executor`)) and collects the results,
+/// in the order of `inputs`.
+///
+/// Unlike [`par_map`], `op` may capture its environment. Each call gets a handle
+/// forked from `db`, like the tasks of [`join`](`crate::join`), and its reads are
+/// recorded as dependencies of the calling query.
+pub fn par_map_with<Db, D, E, C, F>(db: &Db, inputs: impl IntoIterator<Item = D>, op: F) -> C
 where
     Db: Database + ?Sized,
     D: Send,
-    E: Send + Sync,
-    C: FromParallelIterator<E>,
+    E: Send,
+    C: FromIterator<E>,
+    F: Fn(&Db, D) -> E + Sync,
 {
-    let parallel_db = ParallelDb::Ref(db.as_dyn_database());
-
-    inputs
-        .into_par_iter()
-        .map_with(parallel_db, |parallel_db, element| {
-            let db = parallel_db.as_view::<Db>();
-            op(db, element)
-        })
-        .collect()
+    run_parallel(db, inputs, op).collect()
 }
 
-/// This enum _must not_ be public or used outside of `par_map`.
-enum ParallelDb<'db> {
-    Ref(&'db dyn Database),
-    Fork(Box<dyn Database + Send>),
+/// Calls `op` on each of `inputs` in parallel with the executor of the database,
+/// see [`par_map_with`].
+pub fn par_for_each<Db, D, F>(db: &Db, inputs: impl IntoIterator<Item = D>, op: F)
+where
+    Db: Database + ?Sized,
+    D: Send,
+    F: Fn(&Db, D) + Sync,
+{
+    run_parallel(db, inputs, op).for_each(drop)
 }
 
-/// SAFETY: the contents of the database are never accessed on the thread
-/// where this wrapper type is created.
-unsafe impl Send for ParallelDb<'_> {}
+/// Maps `op` over `inputs` in parallel with the executor of the database
+/// and collects the results that are `Some`, see [`par_map_with`].
+pub fn par_filter_map<Db, D, E, C, F>(db: &Db, inputs: impl IntoIterator<Item = D>, op: F) -> C
+where
+    Db: Database + ?Sized,
+    D: Send,
+    E: Send,
+    C: FromIterator<E>,
+    F: Fn(&Db, D) -> Option<E> + Sync,
+{
+    run_parallel(db, inputs, op).flatten().collect()
+}
 
-impl Deref for ParallelDb<'_> {
-    type Target = dyn Database;
+/// Calls `op` on each of `inputs` in parallel with the executor of the database,
+/// with the handles for the tasks, and returns the results in order.
+fn run_parallel<Db, D, E>(
+    db: &Db,
+    inputs: impl IntoIterator<Item = D>,
+    op: impl Fn(&Db, D) -> E + Sync,
+) -> impl Iterator<Item = E>
+where
+    Db: Database + ?Sized,
+    D: Send,
+    E: Send,
+{
+    let inputs: Vec<Mutex<Option<D>>> = inputs
+        .into_iter()
+        .map(|input| Mutex::new(Some(input)))
+        .collect();
+    let outputs: Vec<Mutex<Option<E>>> = inputs.iter().map(|_| Mutex::new(None)).collect();
 
-    fn deref(&self) -> &Self::Target {
-        match self {
-            ParallelDb::Ref(db) => *db,
-            ParallelDb::Fork(db) => db.as_dyn_database(),
-        }
-    }
-}
+    let forks = Forks::new(db.as_dyn_database());
+    db.zalsa().executor().for_each(inputs.len(), &|index| {
+        let input = inputs[index].lock().take().unwrap();
+        let output = forks.run(index, |db| op(db.as_view::<Db>(), input));
+        *outputs[index].lock() = Some(output);
+    });
+    forks.complete();
+    db.zalsa_local()
+        .unwind_if_revision_cancelled(db.as_dyn_database());
 
-impl Clone for ParallelDb<'_> {
-    fn clone(&self) -> Self {
-        ParallelDb::Fork(self.fork_db())
-    }
+    outputs
+        .into_iter()
+        .map(|output| output.into_inner().unwrap())
 }
@@ -0,0 +1,357 @@
+use std::{any::Any, cell::Cell, panic::AssertUnwindSafe, sync::Arc};
+
+use parking_lot::Mutex;
+
+use crate::{
+    active_query::{ActiveQuery, SharedIdentities},
+    attach::attach_fork,
+    executor::Task,
+    key::{DatabaseKeyIndex, DependencyIndex},
+    runtime::StampedValue,
+    zalsa_local::ZalsaLocal,
+    Cancelled, Cycle, Database, Revision, StackOverflow,
+};
+
+/// Runs `a` and `b` in parallel with the executor of the database
+/// (see [`Storage::with_executor`](`crate::Storage::with_executor`)) and returns both results.
+///
+/// Each closure gets a handle to the database; handles are forked from `db`
+/// and reused across tasks. The reads made by the closures are recorded as
+/// dependencies of the calling query. If either closure panics (e.g., because the revision
+/// was cancelled), the panic is propagated to the caller once both have completed.
+///
+/// Closures must not call queries that are executing further up the stack of the
+/// calling query: as the calling query waits for them, this would deadlock.
+pub fn join<Db, A, B, RA, RB>(db: &Db, a: A, b: B) -> (RA, RB)
+where
+    Db: Database + ?Sized,
+    A: FnOnce(&Db) -> RA + Send,
+    B: FnOnce(&Db) -> RB + Send,
+    RA: Send,
+    RB: Send,
+{
+    let forks = Forks::new(db.as_dyn_database());
+    let (mut result_a, mut result_b) = (None, None);
+    db.zalsa().executor().join(
+        Box::new(|| result_a = Some(forks.run(0, |db| a(db.as_view::<Db>())))),
+        Box::new(|| result_b = Some(forks.run(1, |db| b(db.as_view::<Db>())))),
+    );
+    forks.complete();
+    db.zalsa_local()
+        .unwind_if_revision_cancelled(db.as_dyn_database());
+    (result_a.unwrap(), result_b.unwrap())
+}
+
+/// Creates a [`Scope`] in which tasks can be spawned with the executor of the database
+/// (see [`Storage::with_executor`](`crate::Storage::with_executor`)), and waits for all of them
+/// to complete. `op` itself runs on the current thread.
+///
+/// Like with [`join`], tasks get handles forked from `db`, their reads are recorded
+/// as dependencies of the calling query, and the first panic of a task is propagated
+/// to the caller once all tasks have completed.
+pub fn scope<'scope, Db, OP, R>(db: &'scope Db, op: OP) -> R
+where
+    Db: Database + ?Sized,
+    OP: FnOnce(&Scope<'_, 'scope, Db>) -> R,
+{
+    let forks = Arc::new(Forks::new(db.as_dyn_database()));
+    let mut result = None;
+    db.zalsa().executor().scope(Box::new(|spawn| {
+        result = Some(op(&Scope {
+            spawn,
+            forks: &forks,
+            spawned: Cell::new(0),
+            phantom: std::marker::PhantomData,
+        }))
+    }));
+    forks.complete();
+    db.zalsa_local()
+        .unwind_if_revision_cancelled(db.as_dyn_database());
+    result.unwrap()
+}
+
+/// Runs `task` with the executor of the database
+/// (see [`Storage::with_executor`](`crate::Storage::with_executor`)), without waiting for it.
+///
+/// The task gets a handle forked from `db`, like the tasks of [`join`], but its reads are not
+/// recorded as dependencies of the calling query, if any. The handle delays a new revision until
+/// the task completes: if the revision is cancelled, the task unwinds and the cancellation is
+/// caught. Other panics are left to the executor (e.g., a [`RayonExecutor`](`crate::RayonExecutor`)
+/// aborts).
+pub fn spawn<Db, F>(db: &Db, task: F)
+where
+    Db: Database + ?Sized,
+    F: FnOnce(&Db) + Send + 'static,
+{
+    let fork = db.take_fork();
+    db.zalsa().executor().spawn(Box::new(move || {
+        let result = Cancelled::catch(AssertUnwindSafe(|| {
+            let db = fork.as_dyn_database();
+            db.zalsa_local().unwind_if_revision_cancelled(db);
+            attach_fork(db, || task(db.as_view::<Db>()))
+        }));
+        if result.is_ok() {
+            fork.release_fork();
+        }
+    }));
+}
+
+/// A scope for spawning tasks that use the database, see [`scope`].
+pub struct Scope<'s, 'scope, Db: ?Sized> {
+    spawn: &'s dyn Fn(Task<'scope>),
+    forks: &'s Arc<Forks<'scope>>,
+
+    /// The number of tasks spawned so far, i.e., the index of the next task.
+    spawned: Cell<usize>,
+    phantom: std::marker::PhantomData<fn(&Db)>,
+}
+
+impl<'scope, Db> Scope<'_, 'scope, Db>
+where
+    Db: Database + ?Sized,
+{
+    /// Spawns a task that runs with a handle to the database.
+    pub fn spawn<F>(&self, task: F)
+    where
+        F: FnOnce(&Db) + Send + 'scope,
+    {
+        // Take the handle on this thread, which owns the database: `op` may use it meanwhile.
+        let fork = self.forks.take_handle();
+        let forks = self.forks.clone();
+        let index = self.spawned.replace(self.spawned.get() + 1);
+        (self.spawn)(Box::new(move || {
+            forks.run_with(index, fork, |db| task(db.as_view::<Db>()))
+        }));
+    }
+}
+
+/// Handles forked from a database for the tasks of a parallel operation.
+///
+/// A task takes an idle handle of the storage, or forks a new one if there is none, and returns
+/// it when it completes (see [`ZalsaDatabase::take_fork`](`crate::plumbing::ZalsaDatabase::take_fork`)):
+/// so handles are reused across operations, and there are at most as many as tasks running at the
+/// same time (tasks of a [`scope`] take their handle when they are spawned). The storage drops its
+/// idle handles when a new revision starts, so that they do not delay it.
+///
+/// If the operation runs within a query, each task runs in a frame of its own, which is added
+/// to the frame of the query by [`Forks::complete`], in the order of the tasks (e.g., `a` before
+/// `b` for [`join`]), whichever completes first.
+pub(crate) struct Forks<'db> {
+    db: &'db dyn Database,
+
+    /// The idle speculative handles of a [`prefetch`], which are not returned to the storage.
+    /// Locked while forking a handle from `db`, for the other operations too.
+    idle: Mutex<Vec<Box<dyn Database>>>,
+
+    /// If true, the handles verify dependencies speculatively (see [`prefetch`]).
+    speculative: bool,
+
+    /// The query that runs the operation, if any (see `ZalsaLocal::start_tasks`).
+    parent: Option<TaskParent>,
+
+    /// The frames of the tasks that have completed, with the index of their task.
+    tasks: Mutex<Vec<(usize, ActiveQuery)>>,
+}
+
+/// SAFETY: other threads only use `db` to take or fork handles, while `idle` is locked, and only
+/// while the thread that owns `db` waits for the operation to complete ([`join`] and
+/// [`par_map`](`crate::par_map`)). The tasks of a [`scope`] get handles forked by
+/// [`Scope::spawn`], on the thread that owns `db`, as `op` may use `db` while they run.
+unsafe impl Send for Forks<'_> {}
+
+/// SAFETY: see `Send`.
+unsafe impl Sync for Forks<'_> {}
+
+impl<'db> Forks<'db> {
+    pub(crate) fn new(db: &'db dyn Database) -> Self {
+        Self {
+            db,
+            idle: Mutex::new(vec![]),
+            speculative: false,
+            parent: db.zalsa_local().start_tasks(),
+            tasks: Mutex::new(vec![]),
+        }
+    }
+
+    /// Handles for speculative verification, see [`prefetch`]. Reads are not recorded.
+    fn speculative(db: &'db dyn Database, idle: Vec<Box<dyn Database>>) -> Self {
+        Self {
+            db,
+            idle: Mutex::new(idle),
+            speculative: true,
+            parent: None,
+            tasks: Mutex::new(vec![]),
+        }
+    }
+
+    /// Takes an idle handle, or forks a new one if there is none.
+    fn take_handle(&self) -> Box<dyn Database> {
+        let mut idle = self.idle.lock();
+        if self.speculative {
+            idle.pop().unwrap_or_else(|| self.db.fork_db())
+        } else {
+            self.db.take_fork()
+        }
+    }
+
+    /// Returns a handle taken with [`Forks::take_handle`] once its task has completed.
+    fn release_handle(&self, fork: Box<dyn Database>) {
+        if self.speculative {
+            self.idle.lock().push(fork);
+        } else {
+            fork.release_fork();
+        }
+    }
+
+    /// Runs `op`, the task at `index`, with an idle handle.
+    ///
+    /// Unwinds if the revision has been cancelled. If `op` panics, the handle is dropped.
+    pub(crate) fn run<R>(&self, index: usize, op: impl FnOnce(&dyn Database) -> R) -> R {
+        self.run_with(index, self.take_handle(), op)
+    }
+
+    /// Runs `op` with `fork`, taken with [`Forks::take_handle`], see [`Forks::run`].
+    fn run_with<R>(
+        &self,
+        index: usize,
+        fork: Box<dyn Database>,
+        op: impl FnOnce(&dyn Database) -> R,
+    ) -> R {
+        let db = fork.as_dyn_database();
+        if self.speculative {
+            db.zalsa_local().set_speculative();
+        }
+        db.zalsa_local().unwind_if_revision_cancelled(db);
+        let result = attach_fork(db, || match &self.parent {
+            Some(parent) => {
+                let active_query = db.zalsa_local().push_query(
+                    parent.database_key_index,
+                    None,
+                    db.zalsa().max_durability(),
+                );
+                active_query.seed_task(parent, parent.operation.child(index));
+                let result = op(db);
+                self.tasks.lock().push((index, active_query.complete()));
+                result
+            }
+            None => op(db),
+        });
+        self.release_handle(fork);
+        result
+    }
+
+    /// Adds the frames of the tasks to the frame of the calling query, once all tasks have
+    /// completed. Must be called on the thread that created the handles.
+    ///
+    /// Not called if a task panics: the panic is propagated to the calling query.
+    pub(crate) fn complete(&self) {
+        if self.parent.is_some() {
+            let mut tasks = std::mem::take(&mut *self.tasks.lock());
+            tasks.sort_by_key(|&(index, _)| index);
+            let tasks = tasks.into_iter().map(|(_, task)| task);
+            self.db.zalsa_local().complete_tasks(tasks);
+        }
+    }
+}
+
+/// The query that runs a parallel operation, as seen by its tasks (see [`Forks`]).
+pub(crate) struct TaskParent {
+    pub(crate) database_key_index: DatabaseKeyIndex,
+
+    /// The durability and changed-at information of the query when it started the operation.
+    pub(crate) stamp: StampedValue<()>,
+
+    /// The tracked struct identities of the query, shared with the tasks.
+    pub(crate) identities: Arc<Mutex<SharedIdentities>>,
+
+    /// The key of the operation: each task gets a child of it, by its index.
+    pub(crate) operation: TaskKey,
+}
+
+/// Identifies the task of a parallel operation that creates a tracked struct, as part of
+/// its [`IdentityHash`](`crate::tracked_struct::IdentityHash`): so the tasks of a query
+/// disambiguate their tracked structs independently of each other, and the structs get
+/// the same ids whichever task creates its structs first.
+///
+/// The default key is the one of the query itself. The other keys are derived from the key
+/// of the query (or task) that starts the operation, the number of operations it started
+/// before, and the index of the task, which do not depend on the scheduling of the tasks.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub(crate) struct TaskKey(u64);
+
+impl TaskKey {
+    /// The key of the child at `index`: the `index`-th operation started by a task,
+    /// or the `index`-th task of an operation.
+    pub(crate) fn child(self, index: usize) -> TaskKey {
+        TaskKey(crate::hash::hash(&(self.0, index)))
+    }
+}
+
+/// Checks in parallel whether `inputs` changed after `last_verified_at`, on speculative handles,
+/// so that verifying them again afterwards is cheap. The results are discarded.
+///
+/// The speculation is best effort: it only verifies memos and never executes queries, and it gives
+/// up on a dependency that is claimed by another thread (or on a cancellation, a cycle, etc.),
+/// leaving it to the regular verification. Other panics are propagated.
+///
+/// Must be called during a [`PrefetchWalk`], whose handles are reused.
+pub(crate) fn prefetch(db: &dyn Database, inputs: &[DependencyIndex], last_verified_at: Revision) {
+    let zalsa_local = db.zalsa_local();
+    let forks = Forks::speculative(db, zalsa_local.take_prefetch_handles());
+    db.zalsa().executor().for_each(inputs.len(), &|index| {
+        let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            forks.run(index, |db| {
+                inputs[index].maybe_changed_after(db, last_verified_at)
+            })
+        }));
+        if let Err(payload) = result {
+            if !is_salsa_payload(&*payload) {
+                std::panic::resume_unwind(payload);
+            }
+        }
+    });
+    zalsa_local.return_prefetch_handles(forks.idle.into_inner());
+}
+
+/// True for the panic payloads that salsa unwinds queries with.
+fn is_salsa_payload(payload: &(dyn Any + Send)) -> bool {
+    payload.is::<SpeculationAborted>()
+        || payload.is::<Cancelled>()
+        || payload.is::<Cycle>()
+        || payload.is::<StackOverflow>()
+}
+
+/// The verification of a memo with parallel verification enabled, see
+/// [`Storage::with_parallel_verification`](`crate::Storage::with_parallel_verification`).
+///
+/// The outermost walk on a handle keeps the speculative handles of [`prefetch`] for the memos
+/// verified on the way, and drops them when it ends, so that they do not delay a new revision.
+pub(crate) struct PrefetchWalk<'db> {
+    zalsa_local: Option<&'db ZalsaLocal>,
+}
+
+impl<'db> PrefetchWalk<'db> {
+    pub(crate) fn start(db: &'db dyn Database) -> Self {
+        let zalsa_local = db.zalsa_local();
+        Self {
+            zalsa_local: zalsa_local.start_prefetch_walk().then_some(zalsa_local),
+        }
+    }
+}
+
+impl Drop for PrefetchWalk<'_> {
+    fn drop(&mut self) {
+        if let Some(zalsa_local) = self.zalsa_local {
+            zalsa_local.end_prefetch_walk();
+        }
+    }
+}
+
+/// The panic payload that aborts a speculative verification.
+pub(crate) struct SpeculationAborted;
+
+impl SpeculationAborted {
+    pub(crate) fn throw() -> ! {
+        std::panic::resume_unwind(Box::new(SpeculationAborted))
+    }
+}
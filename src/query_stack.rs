@@ -0,0 +1,77 @@
+use std::fmt;
+
+use crate::{attach::with_attached_database, key::DatabaseKeyIndex, Database};
+
+/// The tracked functions active on a thread, e.g. when a panic occurred.
+///
+/// Capturing only copies the keys of the frames: they are rendered (with ingredient names)
+/// when the stack is formatted, using the attached database (see [`Database::attach`]).
+///
+/// Salsa captures the stack when a panic unwinds out of a tracked function:
+/// see [`QueryStack::last_panic`] and [`Cancelled::PropagatedPanic`](`crate::Cancelled::PropagatedPanic`).
+/// To print the stack along with the panic message, use [`QueryStack::install_panic_hook`].
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct QueryStack {
+    /// The active queries, outermost first.
+    frames: Vec<DatabaseKeyIndex>,
+}
+
+impl QueryStack {
+    /// Captures the stack of tracked functions that are currently active
+    /// on this thread. Does not record a dependency.
+    pub fn capture(db: &dyn Database) -> Self {
+        Self::from_frames(db.zalsa_local().active_query_keys())
+    }
+
+    pub(crate) fn from_frames(frames: Vec<DatabaseKeyIndex>) -> Self {
+        Self { frames }
+    }
+
+    /// The stack that was captured when the most recent panic unwound out of a tracked
+    /// function executed with `db` on this thread.
+    ///
+    /// Salsa's own unwinds, for cycles and cancellations, are not panics of the tracked
+    /// functions and do not capture the stack.
+    pub fn last_panic(db: &dyn Database) -> Option<QueryStack> {
+        db.zalsa_local().last_panic_query_stack()
+    }
+
+    /// The active queries, outermost first.
+    pub fn frames(&self) -> &[DatabaseKeyIndex] {
+        &self.frames
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Installs a panic hook that, after running the previously installed hook,
+    /// prints the query stack of the panicking thread (if a database is attached
+    /// and a tracked function is active).
+    pub fn install_panic_hook() {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            previous(info);
+            let stack = with_attached_database(QueryStack::capture).unwrap_or_default();
+            if !stack.is_empty() {
+                eprint!("{stack}");
+            }
+        }));
+    }
+}
+
+impl fmt::Display for QueryStack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "query stack (most recent call first):")?;
+        for (index, frame) in self.frames.iter().rev().enumerate() {
+            writeln!(f, "{index:4}: {frame:?}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for QueryStack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(&self.frames).finish()
+    }
+}
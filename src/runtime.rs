@@ -1,19 +1,28 @@
 use std::{
+    cell::Cell,
     panic::panic_any,
     sync::{atomic::AtomicUsize, Arc},
     thread::ThreadId,
+    time::Duration,
 };
 
 use crossbeam::atomic::AtomicCell;
 use parking_lot::Mutex;
 
 use crate::{
-    active_query::ActiveQuery, cycle::CycleRecoveryStrategy, durability::Durability,
-    key::DatabaseKeyIndex, revision::AtomicRevision, table::Table, zalsa_local::ZalsaLocal,
-    Cancelled, Cycle, Database, Event, EventKind, Revision,
+    active_query::ActiveQuery,
+    cycle::CycleRecoveryStrategy,
+    durability::Durability,
+    executor::{default_executor, Executor},
+    key::DatabaseKeyIndex,
+    revision::AtomicRevision,
+    table::Table,
+    zalsa_local::ZalsaLocal,
+    Cancelled, Cycle, Database, EventKind, EventKindMask, QueryStack, Revision,
 };
 
 use self::dependency_graph::DependencyGraph;
+pub use self::dependency_graph::{BlockedThread, WaitForGraph};
 
 mod dependency_graph;
 
@@ -26,6 +35,11 @@ pub struct Runtime {
     /// is set back to false once the input has been changed.
     revision_canceled: AtomicCell<bool>,
 
+    /// The revision in which the database has been frozen, if it has been
+    /// (see [`Database::freeze`](`crate::Database::freeze`)). A frozen database never
+    /// starts a new revision, so the current revision cannot be canceled.
+    frozen_at: AtomicCell<Option<Revision>>,
+
     /// Stores the "last change" revision for values of each duration.
     /// This vector is always of length at least 1 (for Durability 0)
     /// but its total length depends on the number of durations. The
@@ -41,14 +55,69 @@ pub struct Runtime {
     /// another, waiting for queries to terminate.
     dependency_graph: Mutex<DependencyGraph>,
 
+    /// If set, threads that are blocked for longer than this log the wait-for graph
+    /// (see [`Storage::with_watchdog`](`crate::Storage::with_watchdog`)).
+    watchdog: Option<Duration>,
+
+    /// The maximum depth of the query stack on one thread
+    /// (see [`Storage::with_max_query_depth`](`crate::Storage::with_max_query_depth`)).
+    max_query_depth: Option<usize>,
+
+    /// If set, execution continues on a new thread with a stack of this size when
+    /// `max_query_depth` is reached, instead of unwinding with [`StackOverflow`](`crate::StackOverflow`)
+    /// (see [`Storage::with_overflow_threads`](`crate::Storage::with_overflow_threads`)).
+    overflow_stack_size: Option<usize>,
+
+    /// If true, results that would otherwise depend on thread interleaving are normalized
+    /// (see [`Storage::with_deterministic_execution`](`crate::Storage::with_deterministic_execution`)).
+    deterministic: bool,
+
+    /// Runs the tasks of parallel operations such as [`join`](`crate::join`)
+    /// (see [`Storage::with_executor`](`crate::Storage::with_executor`)).
+    executor: Box<dyn Executor>,
+
+    /// If set, the inputs of memos with at least this many inputs are verified in parallel
+    /// (see [`Storage::with_parallel_verification`](`crate::Storage::with_parallel_verification`)).
+    parallel_verification: Option<usize>,
+
     /// Data for instances
     table: Table,
 }
 
+thread_local! {
+    /// Set on threads that continue the query stack of another thread
+    /// (see [`crate::stack_overflow::continue_on_new_thread`]).
+    static THREAD_ID: Cell<Option<ThreadId>> = const { Cell::new(None) };
+}
+
+/// The id of the thread on whose behalf the current thread executes queries.
+/// This is the id of the current thread, unless it continues the query stack of another thread.
+pub(crate) fn current_thread_id() -> ThreadId {
+    THREAD_ID
+        .get()
+        .unwrap_or_else(|| std::thread::current().id())
+}
+
+/// Runs `op` on behalf of the thread `thread_id`.
+pub(crate) fn with_thread_id<R>(thread_id: ThreadId, op: impl FnOnce() -> R) -> R {
+    let old = THREAD_ID.replace(Some(thread_id));
+    let _reset = ResetThreadId(old);
+    op()
+}
+
+struct ResetThreadId(Option<ThreadId>);
+
+impl Drop for ResetThreadId {
+    fn drop(&mut self) {
+        THREAD_ID.set(self.0);
+    }
+}
+
 #[derive(Clone, Debug)]
 pub(crate) enum WaitResult {
     Completed,
-    Panicked,
+    /// The query panicked; carries the query stack of the panicking thread.
+    Panicked(QueryStack),
     Cycle(Cycle),
 }
 
@@ -78,15 +147,26 @@ impl<V> StampedValue<V> {
     }
 }
 
-impl Default for Runtime {
-    fn default() -> Self {
+impl Runtime {
+    /// Creates a runtime that distinguishes `durabilities` levels of durability.
+    pub(crate) fn new(durabilities: usize) -> Self {
+        assert!(
+            (1..=Durability::MAX_LEVELS).contains(&durabilities),
+            "the number of durability levels must be between 1 and {}",
+            Durability::MAX_LEVELS,
+        );
         Runtime {
-            revisions: (0..Durability::LEN)
-                .map(|_| AtomicRevision::start())
-                .collect(),
+            revisions: (0..durabilities).map(|_| AtomicRevision::start()).collect(),
             next_id: AtomicUsize::new(1),
             revision_canceled: Default::default(),
+            frozen_at: Default::default(),
             dependency_graph: Default::default(),
+            watchdog: None,
+            max_query_depth: None,
+            overflow_stack_size: None,
+            deterministic: false,
+            executor: default_executor(),
+            parallel_verification: None,
             table: Default::default(),
         }
     }
@@ -98,7 +178,12 @@ impl std::fmt::Debug for Runtime {
             .field("revisions", &self.revisions)
             .field("next_id", &self.next_id)
             .field("revision_canceled", &self.revision_canceled)
+            .field("frozen_at", &self.frozen_at)
             .field("dependency_graph", &self.dependency_graph)
+            .field("watchdog", &self.watchdog)
+            .field("max_query_depth", &self.max_query_depth)
+            .field("overflow_stack_size", &self.overflow_stack_size)
+            .field("deterministic", &self.deterministic)
             .finish()
     }
 }
@@ -113,7 +198,7 @@ impl Runtime {
     /// less than or equal to `durability` to the current revision.
     pub(crate) fn report_tracked_write(&mut self, durability: Durability) {
         let new_revision = self.current_revision();
-        for rev in &self.revisions[1..=durability.index()] {
+        for rev in &self.revisions[1..=self.revision_index(durability)] {
             rev.store(new_revision);
         }
     }
@@ -127,7 +212,19 @@ impl Runtime {
     /// dependencies.
     #[inline]
     pub(crate) fn last_changed_revision(&self, d: Durability) -> Revision {
-        self.revisions[d.index()].load()
+        self.revisions[self.revision_index(d)].load()
+    }
+
+    /// The highest durability level distinguished by this runtime.
+    pub(crate) fn max_durability(&self) -> Durability {
+        Durability::new((self.revisions.len() - 1) as u8)
+    }
+
+    /// Index into `revisions` for durability `d`. Durabilities beyond
+    /// the configured number of levels share the highest level.
+    #[inline]
+    fn revision_index(&self, d: Durability) -> usize {
+        d.index().min(self.revisions.len() - 1)
     }
 
     pub(crate) fn load_cancellation_flag(&self) -> bool {
@@ -138,6 +235,66 @@ impl Runtime {
         self.revision_canceled.store(true);
     }
 
+    pub(crate) fn clear_cancellation_flag(&mut self) {
+        self.revision_canceled.store(false);
+    }
+
+    pub(crate) fn set_watchdog(&mut self, timeout: Duration) {
+        self.watchdog = Some(timeout);
+    }
+
+    pub(crate) fn set_max_query_depth(&mut self, max_depth: usize) {
+        self.max_query_depth = Some(max_depth);
+    }
+
+    pub(crate) fn max_query_depth(&self) -> Option<usize> {
+        self.max_query_depth
+    }
+
+    pub(crate) fn set_overflow_stack_size(&mut self, stack_size: usize) {
+        self.overflow_stack_size = Some(stack_size);
+    }
+
+    pub(crate) fn overflow_stack_size(&self) -> Option<usize> {
+        self.overflow_stack_size
+    }
+
+    pub(crate) fn set_deterministic(&mut self) {
+        self.deterministic = true;
+    }
+
+    pub(crate) fn is_deterministic(&self) -> bool {
+        self.deterministic
+    }
+
+    pub(crate) fn set_executor(&mut self, executor: Box<dyn Executor>) {
+        self.executor = executor;
+    }
+
+    pub(crate) fn executor(&self) -> &dyn Executor {
+        &*self.executor
+    }
+
+    pub(crate) fn set_parallel_verification(&mut self, min_inputs: usize) {
+        self.parallel_verification = Some(min_inputs);
+    }
+
+    pub(crate) fn parallel_verification(&self) -> Option<usize> {
+        self.parallel_verification
+    }
+
+    pub(crate) fn wait_for_graph(&self) -> WaitForGraph {
+        self.dependency_graph.lock().wait_for_graph()
+    }
+
+    pub(crate) fn frozen_at(&self) -> Option<Revision> {
+        self.frozen_at.load()
+    }
+
+    pub(crate) fn freeze(&self) {
+        self.frozen_at.store(Some(self.current_revision()));
+    }
+
     pub(crate) fn table(&self) -> &Table {
         &self.table
     }
@@ -185,7 +342,7 @@ impl Runtime {
         query_mutex_guard: QueryMutexGuard,
     ) {
         let mut dg = self.dependency_graph.lock();
-        let thread_id = std::thread::current().id();
+        let thread_id = current_thread_id();
 
         if dg.depends_on(other_id, thread_id) {
             self.unblock_cycle_and_maybe_throw(db, local_state, &mut dg, database_key, other_id);
@@ -195,13 +352,13 @@ impl Runtime {
             assert!(!dg.depends_on(other_id, thread_id));
         }
 
-        db.salsa_event(&|| Event {
-            thread_id,
-            kind: EventKind::WillBlockOn {
-                other_thread_id: other_id,
-                database_key,
-            },
-        });
+        db.zalsa()
+            .report_event(db, EventKindMask::WILL_BLOCK_ON, || {
+                EventKind::WillBlockOn {
+                    other_thread_id: other_id,
+                    database_key,
+                }
+            });
 
         let stack = local_state.take_query_stack();
 
@@ -212,6 +369,7 @@ impl Runtime {
             other_id,
             stack,
             query_mutex_guard,
+            self.watchdog,
         );
 
         local_state.restore_query_stack(stack);
@@ -222,9 +380,11 @@ impl Runtime {
             // If the other thread panicked, then we consider this thread
             // cancelled. The assumption is that the panic will be detected
             // by the other thread and responded to appropriately.
-            WaitResult::Panicked => Cancelled::PropagatedPanic.throw(),
+            WaitResult::Panicked(query_stack) => {
+                local_state.throw_cancelled(Cancelled::PropagatedPanic { query_stack })
+            }
 
-            WaitResult::Cycle(c) => c.throw(),
+            WaitResult::Cycle(c) => local_state.throw_cycle(c),
         }
     }
 
@@ -250,14 +410,15 @@ impl Runtime {
         );
 
         let mut from_stack = local_state.take_query_stack();
-        let from_id = std::thread::current().id();
+        let from_id = current_thread_id();
 
         // Make a "dummy stack frame". As we iterate through the cycle, we will collect the
         // inputs from each participant. Then, if we are participating in cycle recovery, we
         // will propagate those results to all participants.
-        let mut cycle_query = ActiveQuery::new(database_key_index);
+        let mut cycle_query = ActiveQuery::new(database_key_index, self.max_durability());
 
-        // Identify the cycle participants:
+        // Identify the cycle participants, along with the thread executing each of them
+        // and the dependencies they have collected so far:
         let cycle = {
             let mut v = vec![];
             dg.for_each_cycle_participant(
@@ -265,10 +426,12 @@ impl Runtime {
                 &mut from_stack,
                 database_key_index,
                 to_id,
-                |aqs| {
+                |thread_id, aqs| {
                     aqs.iter_mut().for_each(|aq| {
-                        cycle_query.add_from(aq);
-                        v.push(aq.database_key_index);
+                        let mut inputs =
+                            ActiveQuery::new(aq.database_key_index, self.max_durability());
+                        inputs.add_from(aq);
+                        v.push((aq.database_key_index, thread_id, inputs));
                     });
                 },
             );
@@ -279,17 +442,30 @@ impl Runtime {
             // key and rotate it to the front.
             let min = v
                 .iter()
-                .map(|key| (key.ingredient_index.debug_name(db), key))
+                .map(|(key, _, _)| (key.ingredient_index.debug_name(db), key))
                 .min()
                 .unwrap()
                 .1;
-            let index = v.iter().position(|p| p == min).unwrap();
+            let index = v.iter().position(|(p, _, _)| p == min).unwrap();
             v.rotate_left(index);
 
-            // No need to store extra memory.
-            v.shrink_to_fit();
-
-            Cycle::new(Arc::new(v))
+            // Collect the dependencies in the same order, so that they do not depend
+            // on which thread detected the cycle either.
+            let mut participants = Vec::with_capacity(v.len());
+            let mut threads = Vec::with_capacity(v.len());
+            for (key, thread_id, inputs) in v {
+                cycle_query.add_from(&inputs);
+                participants.push(key);
+                threads.push(thread_id);
+            }
+
+            // Where the cycle was closed depends on the thread interleaving.
+            let head = if self.deterministic {
+                participants[0]
+            } else {
+                database_key_index
+            };
+            Cycle::new(Arc::new(participants), threads.into(), head)
         };
         tracing::debug!("cycle {cycle:?}, cycle_query {cycle_query:#?}");
 
@@ -302,25 +478,31 @@ impl Runtime {
         // Mark each cycle participant that has recovery set, along with
         // any frames that come after them on the same thread. Those frames
         // are going to be unwound so that fallback can occur.
-        dg.for_each_cycle_participant(from_id, &mut from_stack, database_key_index, to_id, |aqs| {
-            aqs.iter_mut()
-                .skip_while(|aq| {
-                    match db
-                        .zalsa()
-                        .lookup_ingredient(aq.database_key_index.ingredient_index)
-                        .cycle_recovery_strategy()
-                    {
-                        CycleRecoveryStrategy::Panic => true,
-                        CycleRecoveryStrategy::Fallback => false,
-                    }
-                })
-                .for_each(|aq| {
-                    tracing::debug!("marking {:?} for fallback", aq.database_key_index);
-                    aq.take_inputs_from(&cycle_query);
-                    assert!(aq.cycle.is_none());
-                    aq.cycle = Some(cycle.clone());
-                });
-        });
+        dg.for_each_cycle_participant(
+            from_id,
+            &mut from_stack,
+            database_key_index,
+            to_id,
+            |_, aqs| {
+                aqs.iter_mut()
+                    .skip_while(|aq| {
+                        match db
+                            .zalsa()
+                            .lookup_ingredient(aq.database_key_index.ingredient_index)
+                            .cycle_recovery_strategy()
+                        {
+                            CycleRecoveryStrategy::Panic => true,
+                            CycleRecoveryStrategy::Fallback => false,
+                        }
+                    })
+                    .for_each(|aq| {
+                        tracing::debug!("marking {:?} for fallback", aq.database_key_index);
+                        aq.take_inputs_from(&cycle_query);
+                        assert!(aq.cycle.is_none());
+                        aq.cycle = Some(cycle.clone());
+                    });
+            },
+        );
 
         // Unblock every thread that has cycle recovery with a `WaitResult::Cycle`.
         // They will throw the cycle, which will be caught by the frame that has
@@ -333,7 +515,7 @@ impl Runtime {
         if me_recovered {
             // If the current thread has recovery, we want to throw
             // so that it can begin.
-            cycle.throw()
+            local_state.throw_cycle(cycle)
         } else if others_recovered {
             // If other threads have recovery but we didn't: return and we will block on them.
         } else {
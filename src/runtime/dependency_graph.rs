@@ -1,5 +1,6 @@
 use std::sync::Arc;
 use std::thread::ThreadId;
+use std::time::{Duration, Instant};
 
 use crate::active_query::ActiveQuery;
 use crate::key::DatabaseKeyIndex;
@@ -34,12 +35,85 @@ struct Edge {
     blocked_on_key: DatabaseKeyIndex,
     stack: QueryStack,
 
+    /// When the edge was added.
+    blocked_since: Instant,
+
     /// Signalled whenever a query with dependents completes.
     /// Allows those dependents to check if they are ready to unblock.
     condvar: Arc<parking_lot::Condvar>,
 }
 
+/// A snapshot of the threads that are blocked on queries executing on other threads,
+/// as returned by [`Database::wait_for_graph`](`crate::Database::wait_for_graph`).
+///
+/// Its `Display` impl renders one paragraph per blocked thread; query keys are
+/// rendered with their ingredient names if a database is attached.
+#[derive(Clone, Debug, Default)]
+pub struct WaitForGraph {
+    /// The blocked threads, the longest blocked first.
+    pub blocked: Vec<BlockedThread>,
+}
+
+/// A thread that is blocked on a query executing on another thread.
+#[derive(Clone, Debug)]
+pub struct BlockedThread {
+    /// The blocked thread.
+    pub thread_id: ThreadId,
+
+    /// The query that the thread is waiting for.
+    pub blocked_on_key: DatabaseKeyIndex,
+
+    /// The thread executing `blocked_on_key`.
+    pub blocked_on_thread: ThreadId,
+
+    /// The queries active on the blocked thread, outermost first.
+    pub stack: Vec<DatabaseKeyIndex>,
+
+    /// How long the thread has been blocked.
+    pub blocked_for: Duration,
+}
+
+impl std::fmt::Display for WaitForGraph {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.blocked.is_empty() {
+            return writeln!(f, "no blocked threads");
+        }
+        for thread in &self.blocked {
+            writeln!(
+                f,
+                "{:?} blocked for {:?} on {:?}, executing on {:?}",
+                thread.thread_id,
+                thread.blocked_for,
+                thread.blocked_on_key,
+                thread.blocked_on_thread,
+            )?;
+            for key in &thread.stack {
+                writeln!(f, "    {key:?}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
 impl DependencyGraph {
+    /// Snapshot of the blocked threads, see [`WaitForGraph`].
+    pub(super) fn wait_for_graph(&self) -> WaitForGraph {
+        let now = Instant::now();
+        let mut blocked: Vec<_> = self
+            .edges
+            .iter()
+            .map(|(&thread_id, edge)| BlockedThread {
+                thread_id,
+                blocked_on_key: edge.blocked_on_key,
+                blocked_on_thread: edge.blocked_on_id,
+                stack: edge.stack.iter().map(|aq| aq.database_key_index).collect(),
+                blocked_for: now.saturating_duration_since(edge.blocked_since),
+            })
+            .collect();
+        blocked.sort_by_key(|thread| std::cmp::Reverse(thread.blocked_for));
+        WaitForGraph { blocked }
+    }
+
     /// True if `from_id` depends on `to_id`.
     ///
     /// (i.e., there is a path from `from_id` to `to_id` in the graph.)
@@ -55,7 +129,8 @@ impl DependencyGraph {
         p == to_id
     }
 
-    /// Invokes `closure` with a `&mut ActiveQuery` for each query that participates in the cycle.
+    /// Invokes `closure` with the id of each thread that participates in the cycle, along with
+    /// the `ActiveQuery`s that participate in the cycle on that thread.
     /// The cycle runs as follows:
     ///
     /// 1. The runtime `from_id`, which has the stack `from_stack`, would like to invoke `database_key`...
@@ -67,7 +142,7 @@ impl DependencyGraph {
         from_stack: &mut QueryStack,
         database_key: DatabaseKeyIndex,
         to_id: ThreadId,
-        mut closure: impl FnMut(&mut [ActiveQuery]),
+        mut closure: impl FnMut(ThreadId, &mut [ActiveQuery]),
     ) {
         debug_assert!(self.depends_on(to_id, from_id));
 
@@ -109,7 +184,7 @@ impl DependencyGraph {
                 .iter_mut()
                 .take_while(|p| p.database_key_index != key)
                 .count();
-            closure(&mut edge.stack[prefix..]);
+            closure(id, &mut edge.stack[prefix..]);
             id = edge.blocked_on_id;
             key = edge.blocked_on_key;
         }
@@ -119,7 +194,7 @@ impl DependencyGraph {
             .iter_mut()
             .take_while(|p| p.database_key_index != key)
             .count();
-        closure(&mut from_stack[prefix..]);
+        closure(from_id, &mut from_stack[prefix..]);
     }
 
     /// Unblock each blocked runtime (excluding the current one) if some
@@ -189,6 +264,9 @@ impl DependencyGraph {
     /// This ensures that computing `database_key` doesn't
     /// complete before `block_on` executes.
     ///
+    /// If `watchdog` is set and the thread stays blocked for longer than
+    /// that, the [`WaitForGraph`] is logged (once).
+    ///
     /// Preconditions:
     /// * No path from `to_id` to `from_id`
     ///   (i.e., `me.depends_on(to_id, from_id)` is false)
@@ -200,6 +278,7 @@ impl DependencyGraph {
         to_id: ThreadId,
         from_stack: QueryStack,
         query_mutex_guard: QueryMutexGuard,
+        watchdog: Option<Duration>,
     ) -> (QueryStack, WaitResult) {
         let condvar = me.add_edge(from_id, database_key, to_id, from_stack);
 
@@ -207,12 +286,29 @@ impl DependencyGraph {
         // from completing, now that the edge has been added.
         drop(query_mutex_guard);
 
+        let mut watchdog = watchdog.map(|timeout| Instant::now() + timeout);
         loop {
             if let Some(stack_and_result) = me.wait_results.remove(&from_id) {
                 debug_assert!(!me.edges.contains_key(&from_id));
                 return stack_and_result;
             }
-            condvar.wait(&mut me);
+            match watchdog {
+                None => condvar.wait(&mut me),
+                Some(deadline) => {
+                    if condvar.wait_until(&mut me, deadline).timed_out() {
+                        // Format the snapshot without holding the lock,
+                        // as rendering the keys may take a while.
+                        let graph = me.wait_for_graph();
+                        MutexGuard::unlocked(&mut me, || {
+                            tracing::warn!(
+                                "{from_id:?} blocked on {database_key:?} for longer than expected, \
+                                 wait-for graph:\n{graph}",
+                            );
+                        });
+                        watchdog = None;
+                    }
+                }
+            }
         }
     }
 
@@ -237,6 +333,7 @@ impl DependencyGraph {
                 blocked_on_id: to_id,
                 blocked_on_key: database_key,
                 stack: from_stack,
+                blocked_since: Instant::now(),
                 condvar: condvar.clone(),
             },
         );
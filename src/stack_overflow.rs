@@ -0,0 +1,117 @@
+use std::{
+    fmt,
+    panic::{self, AssertUnwindSafe, UnwindSafe},
+};
+
+use crate::{attach::attach, runtime, Database, QueryStack};
+
+/// A panic payload indicating that the query stack grew deeper than the limit set
+/// with [`Storage::with_max_query_depth`](`crate::Storage::with_max_query_depth`).
+#[derive(Debug)]
+pub struct StackOverflow {
+    max_depth: usize,
+    query_stack: QueryStack,
+}
+
+impl StackOverflow {
+    pub(crate) fn new(max_depth: usize, query_stack: QueryStack) -> Self {
+        Self {
+            max_depth,
+            query_stack,
+        }
+    }
+
+    pub(crate) fn throw(self) -> ! {
+        // We use resume and not panic here to avoid running the panic
+        // hook (that is, to avoid collecting and printing backtrace).
+        std::panic::resume_unwind(Box::new(self));
+    }
+
+    /// Runs `f`, and catches any salsa stack overflow.
+    pub fn catch<F, T>(f: F) -> Result<T, StackOverflow>
+    where
+        F: FnOnce() -> T + UnwindSafe,
+    {
+        match panic::catch_unwind(f) {
+            Ok(t) => Ok(t),
+            Err(payload) => match payload.downcast() {
+                Ok(stack_overflow) => Err(*stack_overflow),
+                Err(payload) => panic::resume_unwind(payload),
+            },
+        }
+    }
+
+    /// The maximum query depth that was exceeded.
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// The query stack, including the query that exceeded the limit.
+    pub fn query_stack(&self) -> &QueryStack {
+        &self.query_stack
+    }
+}
+
+impl fmt::Display for StackOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "query stack exceeded the maximum depth of {}",
+            self.max_depth
+        )
+    }
+}
+
+impl std::error::Error for StackOverflow {}
+
+/// Runs `op` on a new thread with a stack of `stack_size` bytes and waits for it,
+/// resuming any panic on the current thread.
+///
+/// `op` gets a handle forked from `db`, which continues the query stack of `db` (moved to the
+/// fork for the duration of `op`): it counts its query depth from the current depth and acts on
+/// behalf of the current thread (see [`runtime::current_thread_id`]), so that claims and cycles
+/// are detected as usual.
+pub(crate) fn continue_on_new_thread<R: Send>(
+    db: &dyn Database,
+    stack_size: usize,
+    op: impl FnOnce(&dyn Database) -> R + Send,
+) -> R {
+    let thread_id = runtime::current_thread_id();
+    let zalsa_local = db.zalsa_local();
+    let fork = db.fork_db();
+    if zalsa_local.is_speculative() {
+        fork.zalsa_local().set_speculative();
+    }
+    fork.zalsa_local()
+        .continue_query_stack(zalsa_local.take_query_stack());
+
+    let (stack, result) = std::thread::scope(|scope| {
+        std::thread::Builder::new()
+            .name("salsa-stack-overflow".to_string())
+            .stack_size(stack_size)
+            .spawn_scoped(scope, move || {
+                let db = fork.as_dyn_database();
+                let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                    runtime::with_thread_id(thread_id, || attach(db, || op(db)))
+                }));
+                let zalsa_local = db.zalsa_local();
+                let result =
+                    result.map_err(|payload| (payload, zalsa_local.last_panic_query_stack()));
+                (zalsa_local.take_query_stack(), result)
+            })
+            .expect("failed to spawn thread")
+            .join()
+            .expect("panics are caught on the new thread")
+    });
+
+    zalsa_local.restore_query_stack(stack);
+    match result {
+        Ok(result) => result,
+        Err((payload, query_stack)) => {
+            if let Some(query_stack) = query_stack {
+                zalsa_local.resume_panic_query_stack(query_stack);
+            }
+            panic::resume_unwind(payload)
+        }
+    }
+}
@@ -1,11 +1,11 @@
-use std::{marker::PhantomData, panic::RefUnwindSafe, sync::Arc};
+use std::{marker::PhantomData, panic::RefUnwindSafe, sync::Arc, time::Duration};
 
 use parking_lot::{Condvar, Mutex};
 
 use crate::{
     zalsa::{Zalsa, ZalsaDatabase},
     zalsa_local::{self, ZalsaLocal},
-    Database, Event, EventKind,
+    Database, Durability, EventKind, EventKindMask, Executor,
 };
 
 /// Access the "storage" of a Salsa database: this is an internal plumbing trait
@@ -26,7 +26,8 @@ pub struct Storage<Db: Database> {
     /// Reference to the database. This is always `Some` except during destruction.
     zalsa_impl: Option<Arc<Zalsa>>,
 
-    /// Coordination data for cancellation of other handles when `zalsa_mut` is called.
+    /// Coordination data for cancellation of other handles when `zalsa_mut` is called,
+    /// and the idle handles of parallel operations.
     /// This could be stored in Zalsa but it makes things marginally cleaner to keep it separate.
     coordinate: Arc<Coordinate>,
 
@@ -37,27 +38,186 @@ pub struct Storage<Db: Database> {
     phantom: PhantomData<fn() -> Db>,
 }
 struct Coordinate {
+    handles: Mutex<Handles>,
+    cvar: Condvar,
+}
+
+struct Handles {
     /// Counter of the number of clones of actor. Begins at 1.
     /// Incremented when cloned, decremented when dropped.
-    clones: Mutex<usize>,
-    cvar: Condvar,
+    clones: usize,
+
+    /// Handles forked for parallel operations that are not in use, see [`ZalsaDatabase::take_fork`].
+    /// They are counted in `clones`, and dropped when a new revision starts, so that they do not
+    /// delay it, or when all other handles have been dropped, so that they do not keep the
+    /// storage alive.
+    idle: Vec<Box<dyn Database>>,
+}
+
+impl Coordinate {
+    /// Returns the idle handles to drop, if all handles are idle (see [`Handles::idle`]).
+    /// They must be dropped once `handles` is unlocked.
+    fn idle_if_unused(handles: &mut Handles) -> Vec<Box<dyn Database>> {
+        if handles.clones == handles.idle.len() {
+            std::mem::take(&mut handles.idle)
+        } else {
+            vec![]
+        }
+    }
 }
 
 impl<Db: Database> Default for Storage<Db> {
     fn default() -> Self {
+        Self::with_durabilities(Durability::DEFAULT_LEVELS)
+    }
+}
+
+impl<Db: Database> Storage<Db> {
+    /// Creates a storage that distinguishes `levels` levels of [`Durability`],
+    /// from `Durability::new(0)` up to `Durability::new(levels - 1)`.
+    /// The default storage has three levels (`LOW`, `MEDIUM`, and `HIGH`).
+    ///
+    /// Queries whose inputs all have durability `D` skip revalidation in revisions
+    /// where no input of durability `D` or higher changed, so more levels allow to skip
+    /// more work. Durabilities above `levels - 1` are treated like `levels - 1`.
+    ///
+    /// # Panics
+    ///
+    /// If `levels` is zero or greater than 256.
+    pub fn with_durabilities(levels: usize) -> Self {
         Self {
-            zalsa_impl: Some(Arc::new(Zalsa::new::<Db>())),
+            zalsa_impl: Some(Arc::new(Zalsa::new::<Db>(levels))),
             coordinate: Arc::new(Coordinate {
-                clones: Mutex::new(1),
+                handles: Mutex::new(Handles {
+                    clones: 1,
+                    idle: vec![],
+                }),
                 cvar: Default::default(),
             }),
             zalsa_local: ZalsaLocal::new(),
             phantom: PhantomData,
         }
     }
-}
 
-impl<Db: Database> Storage<Db> {
+    /// Enables a watchdog for blocked threads: a thread that waits for longer than
+    /// `timeout` on a query executing on another thread logs the current
+    /// [`WaitForGraph`](`crate::WaitForGraph`) as a `tracing` warning.
+    ///
+    /// # Panics
+    ///
+    /// If the storage has already been cloned.
+    pub fn with_watchdog(mut self, timeout: Duration) -> Self {
+        self.zalsa_mut_before_clone().set_watchdog(timeout);
+        self
+    }
+
+    /// Limits the depth of the query stack: a tracked function call that would
+    /// exceed `max_depth` active queries on one thread unwinds with [`StackOverflow`](`crate::StackOverflow`),
+    /// unless [`Storage::with_overflow_threads`] is used.
+    ///
+    /// # Panics
+    ///
+    /// If the storage has already been cloned.
+    pub fn with_max_query_depth(mut self, max_depth: usize) -> Self {
+        self.zalsa_mut_before_clone().set_max_query_depth(max_depth);
+        self
+    }
+
+    /// When the query stack of a thread reaches the depth set with [`Storage::with_max_query_depth`],
+    /// continue execution on a new thread with a stack of `stack_size` bytes,
+    /// instead of unwinding. The current thread waits for the new thread, so this
+    /// is transparent to the tracked functions.
+    ///
+    /// # Panics
+    ///
+    /// If the storage has already been cloned, or if no maximum query depth was set.
+    pub fn with_overflow_threads(mut self, stack_size: usize) -> Self {
+        let zalsa = self.zalsa_mut_before_clone();
+        assert!(
+            zalsa.max_query_depth().is_some(),
+            "overflow threads require a maximum query depth"
+        );
+        zalsa.set_overflow_stack_size(stack_size);
+        self
+    }
+
+    /// Makes results that would otherwise depend on the interleaving of threads
+    /// deterministic, for reproducible builds:
+    ///
+    /// * The [head](`crate::CycleContext::head`) of a cycle is its first participant,
+    ///   rather than the participant whose call closed the cycle.
+    /// * [`Cycle::render`](`crate::Cycle::render`) does not mark the edges that cross threads.
+    ///
+    /// Independently of this mode, the participants of a cycle are ordered deterministically,
+    /// cycle participants that recover get their dependencies in that order, tracked struct
+    /// disambiguators are counted separately by each task of a parallel operation, and the
+    /// dependencies and accumulated values of the tasks are added to the calling query in task
+    /// order (see [`join`](`crate::join`)). The ids of interned values and tracked structs are
+    /// still allocated in the order in which they are first created.
+    ///
+    /// # Panics
+    ///
+    /// If the storage has already been cloned.
+    pub fn with_deterministic_execution(mut self) -> Self {
+        self.zalsa_mut_before_clone().set_deterministic();
+        self
+    }
+
+    /// Runs the tasks of parallel operations ([`join`](`crate::join`), [`scope`](`crate::scope`),
+    /// [`par_map`](`crate::par_map`) and parallel verification) with `executor`,
+    /// see [`Executor`](`crate::Executor`).
+    ///
+    /// # Panics
+    ///
+    /// If the storage has already been cloned.
+    pub fn with_executor(mut self, executor: impl Executor) -> Self {
+        self.zalsa_mut_before_clone()
+            .set_executor(Box::new(executor));
+        self
+    }
+
+    /// Runs parallel operations ([`join`](`crate::join`), [`scope`](`crate::scope`) and
+    /// [`par_map`](`crate::par_map`)) on a thread pool of `num_threads` threads owned
+    /// by the storage, instead of the global rayon thread pool.
+    ///
+    /// # Panics
+    ///
+    /// If the storage has already been cloned, or if the thread pool cannot be created.
+    #[cfg(feature = "rayon")]
+    pub fn with_threads(self, num_threads: usize) -> Self {
+        let thread_pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .thread_name(|index| format!("salsa-worker-{index}"))
+            .build()
+            .expect("failed to create the thread pool");
+        self.with_executor(crate::RayonExecutor::with_thread_pool(thread_pool))
+    }
+
+    /// Verifies the inputs of memos with at least `min_inputs` inputs in parallel, with the
+    /// executor of the storage (see [`Storage::with_executor`]), which speeds up revalidating
+    /// queries with a big fan-out.
+    ///
+    /// The inputs are still checked one by one, in the order in which they were read,
+    /// to decide whether a memo is valid. Checking them in parallel beforehand is speculative:
+    /// it only verifies memos (and their dependencies), it never executes a query.
+    /// It is skipped if the executor runs tasks one after the other
+    /// (see [`Executor::is_sequential`](`crate::Executor::is_sequential`)).
+    ///
+    /// # Panics
+    ///
+    /// If the storage has already been cloned.
+    pub fn with_parallel_verification(mut self, min_inputs: usize) -> Self {
+        self.zalsa_mut_before_clone()
+            .set_parallel_verification(min_inputs);
+        self
+    }
+
+    /// Access the `Zalsa` mutably, to configure the storage after creating it.
+    fn zalsa_mut_before_clone(&mut self) -> &mut Zalsa {
+        Arc::get_mut(self.zalsa_impl.as_mut().unwrap())
+            .expect("cannot configure the storage after it has been cloned")
+    }
+
     /// Access the `Arc<Zalsa>`. This should always be
     /// possible as `zalsa_impl` only becomes
     /// `None` once we are in the `Drop` impl.
@@ -75,15 +235,18 @@ impl<Db: Database> Storage<Db> {
         let zalsa = self.zalsa_impl();
         zalsa.set_cancellation_flag();
 
-        db.salsa_event(&|| Event {
-            thread_id: std::thread::current().id(),
-
-            kind: EventKind::DidSetCancellationFlag,
+        zalsa.report_event(db, EventKindMask::DID_SET_CANCELLATION_FLAG, || {
+            EventKind::DidSetCancellationFlag
         });
 
-        let mut clones = self.coordinate.clones.lock();
-        while *clones != 1 {
-            self.coordinate.cvar.wait(&mut clones);
+        // Drop the idle handles, outside of the lock: handles that become idle
+        // from now on are dropped by `release_fork`, as the cancellation flag is set.
+        let idle = std::mem::take(&mut self.coordinate.handles.lock().idle);
+        drop(idle);
+
+        let mut handles = self.coordinate.handles.lock();
+        while handles.clones != 1 {
+            self.coordinate.cvar.wait(&mut handles);
         }
     }
     // ANCHOR_END: cancel_other_workers
@@ -95,14 +258,28 @@ unsafe impl<T: HasStorage> ZalsaDatabase for T {
     }
 
     fn zalsa_mut(&mut self) -> &mut Zalsa {
+        assert!(!self.zalsa().is_frozen(), "cannot modify a frozen database");
         self.storage().cancel_others(self);
 
         // The ref count on the `Arc` should now be 1
         let storage = self.storage_mut();
         let arc_zalsa_mut = storage.zalsa_impl.as_mut().unwrap();
         let zalsa_mut = Arc::get_mut(arc_zalsa_mut).unwrap();
-        zalsa_mut.new_revision();
-        zalsa_mut
+
+        // Another handle may have frozen the database while we waited for it to be dropped.
+        if zalsa_mut.is_frozen() {
+            zalsa_mut.clear_cancellation_flag();
+            panic!("cannot modify a frozen database");
+        }
+        let revision = zalsa_mut.new_revision();
+
+        self.zalsa()
+            .report_event(self, EventKindMask::DID_START_NEW_REVISION, || {
+                EventKind::DidStartNewRevision { revision }
+            });
+
+        let storage = self.storage_mut();
+        Arc::get_mut(storage.zalsa_impl.as_mut().unwrap()).unwrap()
     }
 
     fn zalsa_local(&self) -> &ZalsaLocal {
@@ -112,13 +289,32 @@ unsafe impl<T: HasStorage> ZalsaDatabase for T {
     fn fork_db(&self) -> Box<dyn Database> {
         Box::new(self.clone())
     }
+
+    fn take_fork(&self) -> Box<dyn Database> {
+        let idle = self.storage().coordinate.handles.lock().idle.pop();
+        idle.unwrap_or_else(|| self.fork_db())
+    }
+
+    fn release_fork(self: Box<Self>) {
+        let coordinate = Arc::clone(&self.storage().coordinate);
+        let mut handles = coordinate.handles.lock();
+        if self.zalsa().load_cancellation_flag() {
+            drop(handles);
+            drop(self);
+            return;
+        }
+        handles.idle.push(self);
+        let idle = Coordinate::idle_if_unused(&mut handles);
+        drop(handles);
+        drop(idle);
+    }
 }
 
 impl<Db: Database> RefUnwindSafe for Storage<Db> {}
 
 impl<Db: Database> Clone for Storage<Db> {
     fn clone(&self) -> Self {
-        *self.coordinate.clones.lock() += 1;
+        self.coordinate.handles.lock().clones += 1;
 
         Self {
             zalsa_impl: self.zalsa_impl.clone(),
@@ -135,7 +331,13 @@ impl<Db: Database> Drop for Storage<Db> {
         self.zalsa_impl.take();
 
         // *Now* decrement the number of clones and notify once we have completed
-        *self.coordinate.clones.lock() -= 1;
+        let mut handles = self.coordinate.handles.lock();
+        handles.clones -= 1;
+        let idle = Coordinate::idle_if_unused(&mut handles);
+        drop(handles);
         self.coordinate.cvar.notify_all();
+
+        // If this was the last handle in use, drop the idle ones
+        drop(idle);
     }
 }
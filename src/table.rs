@@ -234,6 +234,40 @@ impl<T: Slot> Page<T> {
 
         Ok(make_id(page, SlotIndex(index)))
     }
+
+    /// Allocates contiguous slots for as many of `values` as fit on this page,
+    /// pushing the resulting ids onto `ids`. Stops once the page is full or
+    /// `values` is exhausted, whichever comes first.
+    ///
+    /// The allocation lock is acquired only once for the entire batch. `values` are already
+    /// collected, so that no code of the caller (which may allocate as well) runs under the lock.
+    pub(crate) fn allocate_batch(
+        &self,
+        page: PageIndex,
+        values: &mut std::vec::IntoIter<T>,
+        ids: &mut Vec<Id>,
+    ) {
+        let guard = self.allocation_lock.lock();
+        let mut index = self.allocated.load();
+
+        while index < PAGE_LEN {
+            let Some(value) = values.next() else {
+                break;
+            };
+
+            // Initialize entry `index`
+            let data = &self.data[index];
+            unsafe { std::ptr::write(data.get(), value) };
+
+            // Update the length (this must be done after initialization!)
+            index += 1;
+            self.allocated.store(index);
+
+            ids.push(make_id(page, SlotIndex(index - 1)));
+        }
+
+        drop(guard);
+    }
 }
 
 impl<T: Slot> TablePage for Page<T> {
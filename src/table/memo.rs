@@ -7,7 +7,10 @@ use std::{
 use arc_swap::ArcSwap;
 use parking_lot::RwLock;
 
-use crate::{zalsa::MemoIngredientIndex, zalsa_local::QueryOrigin};
+use crate::{
+    key::DatabaseKeyIndex, zalsa::MemoIngredientIndex, zalsa_local::QueryOrigin, Database,
+    EventKind, EventKindMask, Id,
+};
 
 /// The "memo table" stores the memoized results of tracked function calls.
 /// Every tracked function must take a salsa struct as its first argument
@@ -189,6 +192,34 @@ impl MemoTable {
     }
 }
 
+impl MemoTable {
+    /// Discards all memos in this table, which belongs to the salsa struct `id`.
+    /// Reports a `DidDiscard` event for each memo and removes any outputs
+    /// that the memoized functions created (e.g., tracked structs).
+    pub(crate) fn discard(self, db: &dyn Database, id: Id) {
+        let zalsa = db.zalsa();
+        for (memo_ingredient_index, memo) in self.into_memos() {
+            let ingredient_index = zalsa.ingredient_index_for_memo(memo_ingredient_index);
+
+            let executor = DatabaseKeyIndex {
+                ingredient_index,
+                key_index: id,
+            };
+
+            db.zalsa()
+                .report_event(db, EventKindMask::DID_DISCARD, || EventKind::DidDiscard {
+                    key: executor,
+                });
+
+            for stale_output in memo.origin().outputs() {
+                zalsa
+                    .lookup_ingredient(stale_output.ingredient_index)
+                    .remove_stale_output(db, executor, stale_output.key_index);
+            }
+        }
+    }
+}
+
 impl Drop for MemoEntry {
     fn drop(&mut self) {
         if let Some(MemoEntryData {
@@ -7,6 +7,7 @@ use parking_lot::RwLock;
 
 use crate::{
     key::DatabaseKeyIndex,
+    parallel::SpeculationAborted,
     runtime::WaitResult,
     zalsa::{MemoIngredientIndex, Zalsa},
     zalsa_local::ZalsaLocal,
@@ -34,13 +35,13 @@ impl SyncTable {
     pub(crate) fn claim<'me>(
         &'me self,
         db: &'me dyn Database,
-        zalsa_local: &ZalsaLocal,
+        zalsa_local: &'me ZalsaLocal,
         database_key_index: DatabaseKeyIndex,
         memo_ingredient_index: MemoIngredientIndex,
     ) -> Option<ClaimGuard<'me>> {
         let mut syncs = self.syncs.write();
         let zalsa = db.zalsa();
-        let thread_id = std::thread::current().id();
+        let thread_id = crate::runtime::current_thread_id();
 
         util::ensure_vec_len(&mut syncs, memo_ingredient_index.as_usize() + 1);
 
@@ -54,6 +55,7 @@ impl SyncTable {
                     database_key_index,
                     memo_ingredient_index,
                     zalsa,
+                    zalsa_local,
                     sync_table: self,
                 })
             }
@@ -61,6 +63,13 @@ impl SyncTable {
                 id: other_id,
                 anyone_waiting,
             }) => {
+                // Speculative verification gives up rather than waiting for another
+                // thread, which may well be waiting for the speculation to complete.
+                if zalsa_local.is_speculative() {
+                    drop(syncs);
+                    SpeculationAborted::throw();
+                }
+
                 // NB: `Ordering::Relaxed` is sufficient here,
                 // as there are no loads that are "gated" on this
                 // value. Everything that is written is also protected
@@ -82,6 +91,7 @@ pub(crate) struct ClaimGuard<'me> {
     database_key_index: DatabaseKeyIndex,
     memo_ingredient_index: MemoIngredientIndex,
     zalsa: &'me Zalsa,
+    zalsa_local: &'me ZalsaLocal,
     sync_table: &'me SyncTable,
 }
 
@@ -103,8 +113,10 @@ impl ClaimGuard<'_> {
 
 impl Drop for ClaimGuard<'_> {
     fn drop(&mut self) {
-        let wait_result = if std::thread::panicking() {
-            WaitResult::Panicked
+        // An aborted speculation is not a failure: blocked queries just try again.
+        let wait_result = if std::thread::panicking() && !self.zalsa_local.is_speculative() {
+            let query_stack = self.zalsa_local.last_panic_query_stack();
+            WaitResult::Panicked(query_stack.unwrap_or_default())
         } else {
             WaitResult::Completed
         };
@@ -0,0 +1,643 @@
+//! Collections whose elements are tracked individually.
+//!
+//! Reading an input field records a dependency on the *entire* field value.
+//! If a field holds a [`TrackedVec`] or [`TrackedMap`] instead, reading the field only
+//! depends on the *structure* of the collection (its length or keys); reading an
+//! element records a dependency on that element alone. Elements can be set one at a time,
+//! which only invalidates the queries that read that particular element.
+//!
+//! Adding or removing elements changes the structure of the collection and is done by
+//! setting the field to a new collection, as with any other field value.
+//!
+//! The slots of the elements are released when the last clone of a collection is dropped,
+//! and re-used by collections created from the next revision on.
+
+use std::{
+    any::Any,
+    borrow::Borrow,
+    cell::UnsafeCell,
+    fmt,
+    hash::Hash,
+    marker::PhantomData,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use crossbeam::queue::SegQueue;
+use indexmap::IndexMap;
+
+use crate::{
+    accumulator::accumulated_map::AccumulatedMap,
+    cycle::CycleRecoveryStrategy,
+    hash::FxHasher,
+    ingredient::{fmt_index, Ingredient, Jar, JarAux},
+    key::{DatabaseKeyIndex, DependencyIndex},
+    runtime::{stamp, Stamp},
+    table::{memo::MemoTable, sync::SyncTable, Slot, Table},
+    zalsa::IngredientIndex,
+    zalsa_local::QueryOrigin,
+    Database, Durability, Id, Revision, Runtime,
+};
+
+/// A vector stored in an input field whose elements are tracked individually.
+///
+/// See the [module-level documentation](`self`) for details.
+pub struct TrackedVec<T: Send + Sync + 'static> {
+    ingredient_index: IngredientIndex,
+    ids: Arc<Elements<Box<[Id]>>>,
+    phantom: PhantomData<fn() -> T>,
+}
+
+impl<T: Send + Sync + 'static> TrackedVec<T> {
+    /// Creates a new vector with the given elements, all with [`Durability::LOW`].
+    pub fn new<Db: ?Sized + Database>(db: &Db, elements: impl IntoIterator<Item = T>) -> Self {
+        Self::with_durability(db, elements, Durability::LOW)
+    }
+
+    /// Creates a new vector with the given elements, all with the given durability.
+    pub fn with_durability<Db: ?Sized + Database>(
+        db: &Db,
+        elements: impl IntoIterator<Item = T>,
+        durability: Durability,
+    ) -> Self {
+        let (ingredient_index, ids) = new_elements(
+            db.as_dyn_database(),
+            elements.into_iter(),
+            durability,
+            Vec::into_boxed_slice,
+        );
+        Self {
+            ingredient_index,
+            ids,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Number of elements. Reading the length does not record any dependency
+    /// beyond the one recorded when reading the field that holds this vector.
+    pub fn len(&self) -> usize {
+        self.ids.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.ids.is_empty()
+    }
+
+    /// Returns the element at `index` (if any), recording a dependency on that element only.
+    pub fn get<'db, Db: ?Sized + Database>(&self, db: &'db Db, index: usize) -> Option<&'db T> {
+        let id = *self.ids.ids.get(index)?;
+        Some(read_element(
+            db.as_dyn_database(),
+            self.ingredient_index,
+            id,
+        ))
+    }
+
+    /// Iterates over the elements, recording a dependency on each element as it is read.
+    pub fn iter<'db, Db: ?Sized + Database>(
+        &'db self,
+        db: &'db Db,
+    ) -> impl Iterator<Item = &'db T> + 'db {
+        self.ids
+            .ids
+            .iter()
+            .map(|&id| read_element(db.as_dyn_database(), self.ingredient_index, id))
+    }
+
+    /// Sets the element at `index` to `value`, returning the old value.
+    /// Only queries that read this element will be invalidated.
+    ///
+    /// Like an input setter, this starts a new revision (cancelling other database handles).
+    ///
+    /// # Panics
+    ///
+    /// If `index` is out of bounds.
+    pub fn set<Db: ?Sized + Database>(&self, db: &mut Db, index: usize, value: T) -> T {
+        let id = self.ids.ids[index];
+        set_element(db.as_dyn_database_mut(), self.ingredient_index, id, value)
+    }
+}
+
+impl<T: Send + Sync + 'static> Clone for TrackedVec<T> {
+    fn clone(&self) -> Self {
+        Self {
+            ingredient_index: self.ingredient_index,
+            ids: self.ids.clone(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+/// Two vectors are equal if they refer to the same elements
+/// (i.e., one is a clone of the other), regardless of their contents.
+impl<T: Send + Sync + 'static> PartialEq for TrackedVec<T> {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.ids, &other.ids)
+    }
+}
+
+impl<T: Send + Sync + 'static> Eq for TrackedVec<T> {}
+
+impl<T: Send + Sync + 'static> fmt::Debug for TrackedVec<T>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::attach::with_attached_database(|db| {
+            f.debug_list()
+                .entries(self.ids.ids.iter().map(|&id| leak_element::<T>(db, id)))
+                .finish()
+        })
+        .unwrap_or_else(|| f.debug_list().entries(self.ids.ids.iter()).finish())
+    }
+}
+
+/// A map stored in an input field whose values are tracked individually.
+///
+/// See the [module-level documentation](`self`) for details.
+pub struct TrackedMap<K, V: Send + Sync + 'static> {
+    ingredient_index: IngredientIndex,
+    ids: Arc<Elements<IndexMap<K, Id, FxHasher>>>,
+    phantom: PhantomData<fn() -> V>,
+}
+
+impl<K: Hash + Eq, V: Send + Sync + 'static> TrackedMap<K, V> {
+    /// Creates a new map with the given entries, all with [`Durability::LOW`].
+    pub fn new<Db: ?Sized + Database>(db: &Db, entries: impl IntoIterator<Item = (K, V)>) -> Self {
+        Self::with_durability(db, entries, Durability::LOW)
+    }
+
+    /// Creates a new map with the given entries, all with the given durability.
+    /// If a key appears more than once, the last value for that key wins,
+    /// at the position of the first occurrence of the key.
+    pub fn with_durability<Db: ?Sized + Database>(
+        db: &Db,
+        entries: impl IntoIterator<Item = (K, V)>,
+        durability: Durability,
+    ) -> Self {
+        // Remove repeated keys first: each value gets a slot, which is only released
+        // if its id is in the map.
+        let entries: IndexMap<K, V, FxHasher> = entries.into_iter().collect();
+        let (keys, values): (Vec<K>, Vec<V>) = entries.into_iter().unzip();
+        let (ingredient_index, ids) = new_elements(
+            db.as_dyn_database(),
+            values.into_iter(),
+            durability,
+            |ids| keys.into_iter().zip(ids).collect(),
+        );
+        Self {
+            ingredient_index,
+            ids,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Number of entries. Reading the length does not record any dependency
+    /// beyond the one recorded when reading the field that holds this map.
+    pub fn len(&self) -> usize {
+        self.ids.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.ids.is_empty()
+    }
+
+    /// True if the map contains `key`. Like [`Self::len`], this does not record any dependency.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.ids.ids.contains_key(key)
+    }
+
+    /// Iterates over the keys in insertion order. Like [`Self::len`], this does not record any dependency.
+    pub fn keys(&self) -> impl Iterator<Item = &K> + '_ {
+        self.ids.ids.keys()
+    }
+
+    /// Returns the value for `key` (if any), recording a dependency on that value only.
+    pub fn get<'db, Db: ?Sized + Database, Q>(&self, db: &'db Db, key: &Q) -> Option<&'db V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let id = *self.ids.ids.get(key)?;
+        Some(read_element(
+            db.as_dyn_database(),
+            self.ingredient_index,
+            id,
+        ))
+    }
+
+    /// Iterates over the entries in insertion order,
+    /// recording a dependency on each value as it is read.
+    pub fn iter<'db, Db: ?Sized + Database>(
+        &'db self,
+        db: &'db Db,
+    ) -> impl Iterator<Item = (&'db K, &'db V)> + 'db {
+        self.ids.ids.iter().map(|(key, &id)| {
+            (
+                key,
+                read_element(db.as_dyn_database(), self.ingredient_index, id),
+            )
+        })
+    }
+
+    /// Sets the value for `key` to `value`, returning the old value.
+    /// Only queries that read this value will be invalidated.
+    ///
+    /// Like an input setter, this starts a new revision (cancelling other database handles).
+    ///
+    /// # Panics
+    ///
+    /// If the map does not contain `key`. Adding keys changes the structure of the map,
+    /// which requires setting the field to a new map.
+    pub fn set<Db: ?Sized + Database, Q>(&self, db: &mut Db, key: &Q, value: V) -> V
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let Some(&id) = self.ids.ids.get(key) else {
+            panic!("cannot set the value of a key that is not in the map")
+        };
+        set_element(db.as_dyn_database_mut(), self.ingredient_index, id, value)
+    }
+}
+
+impl<K, V: Send + Sync + 'static> Clone for TrackedMap<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            ingredient_index: self.ingredient_index,
+            ids: self.ids.clone(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+/// Two maps are equal if they refer to the same values
+/// (i.e., one is a clone of the other), regardless of their contents.
+impl<K: Hash + Eq, V: Send + Sync + 'static> PartialEq for TrackedMap<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.ids, &other.ids)
+    }
+}
+
+impl<K: Hash + Eq, V: Send + Sync + 'static> Eq for TrackedMap<K, V> {}
+
+impl<K: fmt::Debug, V: Send + Sync + 'static> fmt::Debug for TrackedMap<K, V>
+where
+    V: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::attach::with_attached_database(|db| {
+            f.debug_map()
+                .entries(
+                    self.ids
+                        .ids
+                        .iter()
+                        .map(|(key, &id)| (key, leak_element::<V>(db, id))),
+                )
+                .finish()
+        })
+        .unwrap_or_else(|| f.debug_map().entries(self.ids.ids.iter()).finish())
+    }
+}
+
+/// The ids of the elements of a collection: a slice for [`TrackedVec`], a map for [`TrackedMap`].
+///
+/// Once the last clone of the collection is dropped, the elements can no longer be read,
+/// so their slots are released (see `IngredientImpl::reset_for_new_revision`).
+struct Elements<I: ElementIds> {
+    ids: I,
+    released: Arc<SegQueue<Id>>,
+}
+
+trait ElementIds {
+    fn for_each_id(&self, op: impl FnMut(Id));
+}
+
+impl ElementIds for Box<[Id]> {
+    fn for_each_id(&self, op: impl FnMut(Id)) {
+        self.iter().copied().for_each(op)
+    }
+}
+
+impl<K> ElementIds for IndexMap<K, Id, FxHasher> {
+    fn for_each_id(&self, op: impl FnMut(Id)) {
+        self.values().copied().for_each(op)
+    }
+}
+
+impl<I: ElementIds> Drop for Elements<I> {
+    fn drop(&mut self) {
+        self.ids.for_each_id(|id| self.released.push(id));
+    }
+}
+
+/// Stores each of `values` in a slot, re-using released slots first,
+/// and returns the ingredient and the ids of the slots, collected with `collect`.
+fn new_elements<V: Send + Sync + 'static, I: ElementIds>(
+    db: &dyn Database,
+    mut values: impl Iterator<Item = V>,
+    durability: Durability,
+    collect: impl FnOnce(Vec<Id>) -> I,
+) -> (IngredientIndex, Arc<Elements<I>>) {
+    let (zalsa, zalsa_local) = db.zalsas();
+    let ingredient_index = zalsa.add_or_lookup_jar_by_type(&JarImpl::<V>::default());
+    let ingredient = zalsa
+        .lookup_ingredient(ingredient_index)
+        .assert_type::<IngredientImpl<V>>();
+    let stamp = stamp(zalsa.current_revision(), durability);
+
+    let mut ids = vec![];
+    while let Some(id) = ingredient.free_list.pop() {
+        let Some(value) = values.next() else {
+            ingredient.free_list.push(id);
+            break;
+        };
+        ids.push(ingredient.reuse(zalsa.table(), id, value, stamp));
+    }
+    let values = values.map(|value| Value {
+        value: UnsafeCell::new(Some(value)),
+        stamp: UnsafeCell::new(stamp),
+        released: AtomicBool::new(false),
+        memos: Default::default(),
+        syncs: Default::default(),
+    });
+    ids.extend(zalsa_local.allocate_batch(zalsa.table(), ingredient_index, values));
+
+    let elements = Elements {
+        ids: collect(ids),
+        released: ingredient.released.clone(),
+    };
+    (ingredient_index, Arc::new(elements))
+}
+
+fn read_element<V: Send + Sync + 'static>(
+    db: &dyn Database,
+    ingredient_index: IngredientIndex,
+    id: Id,
+) -> &V {
+    let (zalsa, zalsa_local) = db.zalsas();
+    let data = IngredientImpl::<V>::data(zalsa.table(), id);
+    let stamp = data.stamp();
+    zalsa_local.report_tracked_read(
+        DependencyIndex {
+            ingredient_index,
+            key_index: Some(id),
+        },
+        stamp.durability,
+        stamp.changed_at,
+    );
+    data.value()
+}
+
+/// Peek at an element without recording any read dependency.
+/// Used for debug printouts.
+fn leak_element<V: Send + Sync + 'static>(db: &dyn Database, id: Id) -> &V {
+    IngredientImpl::<V>::data(db.zalsa().table(), id).value()
+}
+
+fn set_element<V: Send + Sync + 'static>(
+    db: &mut dyn Database,
+    ingredient_index: IngredientIndex,
+    id: Id,
+    value: V,
+) -> V {
+    let (ingredient, runtime) = db.zalsa_mut().lookup_ingredient_mut(ingredient_index);
+    ingredient
+        .assert_type_mut::<IngredientImpl<V>>()
+        .set_element(runtime, id, value)
+}
+
+struct JarImpl<V> {
+    phantom: PhantomData<fn() -> V>,
+}
+
+impl<V> Default for JarImpl<V> {
+    fn default() -> Self {
+        Self {
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<V: Send + Sync + 'static> Jar for JarImpl<V> {
+    fn create_ingredients(
+        &self,
+        _aux: &dyn JarAux,
+        first_index: IngredientIndex,
+    ) -> Vec<Box<dyn Ingredient>> {
+        vec![Box::new(IngredientImpl::<V> {
+            ingredient_index: first_index,
+            free_list: Default::default(),
+            released: Default::default(),
+            phantom: PhantomData,
+        })]
+    }
+}
+
+/// Ingredient that stores the elements of all tracked collections with values of type `V`.
+/// Each element occupies its own slot in the table and carries its own stamp.
+struct IngredientImpl<V> {
+    ingredient_index: IngredientIndex,
+
+    /// Ids of released slots, available for re-use
+    free_list: SegQueue<Id>,
+
+    /// Ids of the slots released by dropped collections since the last revision,
+    /// see [`Elements`]: the elements may still be referenced until the next revision.
+    released: Arc<SegQueue<Id>>,
+
+    phantom: PhantomData<fn() -> Value<V>>,
+}
+
+impl<V: Send + Sync + 'static> IngredientImpl<V> {
+    fn data(table: &Table, id: Id) -> &Value<V> {
+        table.get(id)
+    }
+
+    /// Stores `value` in the released slot `id`, taken from the free list.
+    fn reuse(&self, table: &Table, id: Id, value: V, stamp: Stamp) -> Id {
+        let data_raw = table.get_raw::<Value<V>>(id);
+
+        // SAFETY: Ids only enter the free list in `reset_for_new_revision`, which runs with
+        // `&mut` access to the runtime, after `Drop for Elements` pushed them to the `released`
+        // queue. So the last collection holding `id` is gone: elements are only read through
+        // the ids of a collection. Stale dependency edges may still create `&Value<V>` for the
+        // slot, but the value and stamp are in `UnsafeCell`s, and stale edges load the `released`
+        // field before taking a reference to the stamp (see `maybe_changed_after`), never to the
+        // value. Popping `id` from the free list makes us the only writer; readers of the stamp
+        // see the new one once the `Release` store below clears `released`. The memos and syncs
+        // are always empty.
+        unsafe {
+            let data = &*data_raw;
+            assert!(
+                data.released.load(Ordering::Acquire),
+                "free list entry for `{id:?}` was not released"
+            );
+            *data.value.get() = Some(value);
+            *data.stamp.get() = stamp;
+            data.released.store(false, Ordering::Release);
+        }
+
+        id
+    }
+
+    fn set_element(&mut self, runtime: &mut Runtime, id: Id, value: V) -> V {
+        let r = runtime.table().get_raw::<Value<V>>(id);
+
+        // SAFETY: We hold `&mut` on the runtime so no `&`-references can be active.
+        // Also, we don't access any other data from the table while `r` is active.
+        let r = unsafe { &mut *r };
+
+        let stamp = r.stamp.get_mut();
+        if stamp.durability != Durability::LOW {
+            runtime.report_tracked_write(stamp.durability);
+        }
+        stamp.changed_at = runtime.current_revision();
+        std::mem::replace(r.value.get_mut().as_mut().unwrap(), value)
+    }
+}
+
+impl<V: Send + Sync + 'static> Ingredient for IngredientImpl<V> {
+    fn ingredient_index(&self) -> IngredientIndex {
+        self.ingredient_index
+    }
+
+    fn maybe_changed_after(
+        &self,
+        db: &dyn Database,
+        input: Option<Id>,
+        revision: Revision,
+    ) -> bool {
+        let data = Self::data(db.zalsa().table(), input.unwrap());
+        // Released elements can no longer be read, so they count as changed.
+        data.released.load(Ordering::Acquire) || data.stamp().changed_at > revision
+    }
+
+    fn cycle_recovery_strategy(&self) -> CycleRecoveryStrategy {
+        CycleRecoveryStrategy::Panic
+    }
+
+    fn origin(&self, _db: &dyn Database, _key_index: Id) -> Option<QueryOrigin> {
+        None
+    }
+
+    fn mark_validated_output(
+        &self,
+        _db: &dyn Database,
+        executor: DatabaseKeyIndex,
+        output_key: Option<Id>,
+    ) {
+        unreachable!(
+            "mark_validated_output({:?}, {:?}): tracked collection elements cannot be the output of a tracked function",
+            executor, output_key
+        );
+    }
+
+    fn remove_stale_output(
+        &self,
+        _db: &dyn Database,
+        executor: DatabaseKeyIndex,
+        stale_output_key: Option<Id>,
+    ) {
+        unreachable!(
+            "remove_stale_output({:?}, {:?}): tracked collection elements cannot be the output of a tracked function",
+            executor, stale_output_key
+        );
+    }
+
+    fn requires_reset_for_new_revision(&self) -> bool {
+        true
+    }
+
+    fn reset_for_new_revision(&mut self, runtime: &mut Runtime) {
+        while let Some(id) = self.released.pop() {
+            // SAFETY: We hold `&mut` on the runtime so no `&`-references can be active.
+            let r = unsafe { &mut *runtime.table().get_raw::<Value<V>>(id) };
+            *r.value.get_mut() = None;
+            *r.released.get_mut() = true;
+            self.free_list.push(id);
+        }
+    }
+
+    fn fmt_index(&self, index: Option<Id>, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_index(self.debug_name(), index, fmt)
+    }
+
+    fn debug_name(&self) -> &'static str {
+        "TrackedElement"
+    }
+
+    fn accumulated<'db>(
+        &'db self,
+        _db: &'db dyn Database,
+        _key_index: Id,
+    ) -> Option<&'db AccumulatedMap> {
+        None
+    }
+}
+
+impl<V: Any> fmt::Debug for IngredientImpl<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct(std::any::type_name::<Self>())
+            .field("index", &self.ingredient_index)
+            .finish()
+    }
+}
+
+struct Value<V> {
+    /// The current value of the element, `None` once its slot has been released.
+    ///
+    /// In an `UnsafeCell`, as the element that re-uses a released slot writes it
+    /// while stale dependency edges may still access the slot.
+    value: UnsafeCell<Option<V>>,
+
+    /// The revision and durability information: when did this element last change.
+    /// In an `UnsafeCell`, like `value`.
+    stamp: UnsafeCell<Stamp>,
+
+    /// True if the slot has been released and not re-used yet.
+    /// The value and stamp of a released slot are only accessed with `&mut` access
+    /// to the database, or by the element that re-uses the slot (see `IngredientImpl::reuse`).
+    released: AtomicBool,
+
+    /// Memos (always empty: tracked functions cannot take elements as arguments)
+    memos: MemoTable,
+
+    /// Syncs (always empty, see above)
+    syncs: SyncTable,
+}
+
+impl<V> Value<V> {
+    fn value(&self) -> &V {
+        // SAFETY: Values are read through the ids of a collection, so the slot is not released:
+        // its value is only written with `&mut` access to the database, as `IngredientImpl::reuse`
+        // only writes released slots.
+        unsafe { (*self.value.get()).as_ref() }.expect("access to a released element")
+    }
+
+    /// The stamp of an element that is not released.
+    fn stamp(&self) -> &Stamp {
+        // SAFETY: The slot is not released, see `value`.
+        unsafe { &*self.stamp.get() }
+    }
+}
+
+// SAFETY: The value and stamp in `UnsafeCell`s are `Sync`, and they are only written
+// with `&mut` access or while no references to them exist (see `IngredientImpl::reuse`).
+unsafe impl<V: Sync> Sync for Value<V> {}
+
+impl<V: Send + Sync + 'static> Slot for Value<V> {
+    unsafe fn memos(&self, _current_revision: Revision) -> &MemoTable {
+        &self.memos
+    }
+
+    unsafe fn syncs(&self, _current_revision: Revision) -> &SyncTable {
+        &self.syncs
+    }
+}
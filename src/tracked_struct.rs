@@ -7,13 +7,14 @@ use crate::{
     cycle::CycleRecoveryStrategy,
     ingredient::{fmt_index, Ingredient, Jar, JarAux},
     key::{DatabaseKeyIndex, DependencyIndex},
+    parallel::TaskKey,
     plumbing::ZalsaLocal,
     runtime::StampedValue,
     salsa_struct::SalsaStructInDb,
     table::{memo::MemoTable, sync::SyncTable, Slot, Table},
     zalsa::{IngredientIndex, Zalsa},
     zalsa_local::QueryOrigin,
-    Database, Durability, Event, EventKind, Id, Revision,
+    Database, Durability, EventKindMask, Id, Revision, Runtime,
 };
 
 pub mod tracked_field;
@@ -157,13 +158,21 @@ pub(crate) struct Identity {
 }
 
 impl Identity {
+    pub(crate) fn new(identity_hash: IdentityHash, disambiguator: Disambiguator) -> Self {
+        Self {
+            identity_hash,
+            disambiguator,
+        }
+    }
+
     pub(crate) fn ingredient_index(&self) -> IngredientIndex {
         self.identity_hash.ingredient_index
     }
 }
 
 /// Stores the data that (almost) uniquely identifies a tracked struct.
-/// This includes the ingredient index of that struct type plus the hash of its id fields.
+/// This includes the ingredient index of that struct type plus the hash of its id fields,
+/// and the parallel task of the active query that creates it, if any.
 /// This is mapped to a disambiguator -- a value that starts as 0 but increments each round,
 /// allowing for multiple tracked structs with the same hash and ingredient_index
 /// created within the query to each have a unique id.
@@ -172,10 +181,20 @@ pub struct IdentityHash {
     /// Index of the tracked struct ingredient.
     ingredient_index: IngredientIndex,
 
+    /// The task that creates the struct, see [`TaskKey`].
+    task: TaskKey,
+
     /// Hash of the id fields.
     hash: u64,
 }
 
+impl IdentityHash {
+    /// The identity of a struct with this hash created by `task`.
+    pub(crate) fn for_task(self, task: TaskKey) -> Self {
+        Self { task, ..self }
+    }
+}
+
 // ANCHOR: ValueStruct
 #[derive(Debug)]
 pub struct Value<C>
@@ -276,16 +295,11 @@ where
 
         let identity_hash = IdentityHash {
             ingredient_index: self.ingredient_index,
+            task: TaskKey::default(),
             hash: crate::hash::hash(&C::id_fields(&fields)),
         };
 
-        let (current_deps, disambiguator) = zalsa_local.disambiguate(identity_hash);
-
-        let identity = Identity {
-            identity_hash,
-
-            disambiguator,
-        };
+        let (current_deps, identity) = zalsa_local.disambiguate(identity_hash);
 
         let current_revision = zalsa.current_revision();
         match zalsa_local.tracked_struct_id(&identity) {
@@ -464,11 +478,10 @@ where
     /// unspecified results (but not UB). See [`InternedIngredient::delete_index`] for more
     /// discussion and important considerations.
     pub(crate) fn delete_entity(&self, db: &dyn crate::Database, id: Id) {
-        db.salsa_event(&|| Event {
-            thread_id: std::thread::current().id(),
-            kind: crate::EventKind::DidDiscard {
+        db.zalsa().report_event(db, EventKindMask::DID_DISCARD, || {
+            crate::EventKind::DidDiscard {
                 key: self.database_key_index(id),
-            },
+            }
         });
 
         let zalsa = db.zalsa();
@@ -500,25 +513,7 @@ where
         // Take the memo table. This is safe because we have modified `data_ref.updated_at` to `None`
         // and the code that references the memo-table has a read-lock.
         let memo_table = unsafe { (*data).take_memo_table() };
-        for (memo_ingredient_index, memo) in memo_table.into_memos() {
-            let ingredient_index = zalsa.ingredient_index_for_memo(memo_ingredient_index);
-
-            let executor = DatabaseKeyIndex {
-                ingredient_index,
-                key_index: id,
-            };
-
-            db.salsa_event(&|| Event {
-                thread_id: std::thread::current().id(),
-                kind: EventKind::DidDiscard { key: executor },
-            });
-
-            for stale_output in memo.origin().outputs() {
-                zalsa
-                    .lookup_ingredient(stale_output.ingredient_index)
-                    .remove_stale_output(db, executor, stale_output.key_index);
-            }
-        }
+        memo_table.discard(db, id);
 
         // now that all cleanup has occurred, make available for re-use
         self.free_list.push(id);
@@ -628,7 +623,7 @@ where
         false
     }
 
-    fn reset_for_new_revision(&mut self) {}
+    fn reset_for_new_revision(&mut self, _runtime: &mut Runtime) {}
 
     fn accumulated<'db>(
         &'db self,
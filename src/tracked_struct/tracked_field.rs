@@ -1,6 +1,6 @@
 use std::marker::PhantomData;
 
-use crate::{ingredient::Ingredient, zalsa::IngredientIndex, Database, Id};
+use crate::{ingredient::Ingredient, zalsa::IngredientIndex, Database, Id, Runtime};
 
 use super::{Configuration, Value};
 
@@ -91,7 +91,7 @@ where
         false
     }
 
-    fn reset_for_new_revision(&mut self) {
+    fn reset_for_new_revision(&mut self, _runtime: &mut Runtime) {
         panic!("tracked field ingredients do not require reset")
     }
 
@@ -120,6 +120,12 @@ impl Views {
         assert_eq!(self.source_type_id, db_type_id, "database type mismatch");
 
         let view_type_id = TypeId::of::<DbView>();
+        if view_type_id == TypeId::of::<dyn Database>() {
+            // SAFETY: `DbView` is `dyn Database`, as checked by the type ids. Its view is only
+            // added once a tracked function is called, e.g., not yet when spawning a task.
+            return Some(unsafe { std::mem::transmute_copy::<&dyn Database, &DbView>(&db) });
+        }
+
         for view in self.view_casters.iter() {
             if view.target_type_id == view_type_id {
                 // SAFETY: We verified that this is the view caster for the
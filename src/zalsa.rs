@@ -3,18 +3,23 @@ use parking_lot::Mutex;
 use rustc_hash::FxHashMap;
 use std::any::{Any, TypeId};
 use std::marker::PhantomData;
+use std::sync::OnceLock;
 use std::thread::ThreadId;
+use std::time::Duration;
 
 use crate::cycle::CycleRecoveryStrategy;
+use crate::executor::Executor;
 use crate::ingredient::{Ingredient, Jar, JarAux};
 use crate::nonce::{Nonce, NonceGenerator};
-use crate::runtime::{Runtime, WaitResult};
+use crate::runtime::{Runtime, WaitForGraph, WaitResult};
 use crate::table::memo::MemoTable;
 use crate::table::sync::SyncTable;
 use crate::table::Table;
 use crate::views::Views;
 use crate::zalsa_local::ZalsaLocal;
-use crate::{Database, DatabaseKeyIndex, Durability, Id, Revision};
+use crate::{
+    Database, DatabaseKeyIndex, Durability, Event, EventKind, EventKindMask, Id, Revision,
+};
 
 /// Internal plumbing trait.
 ///
@@ -54,6 +59,15 @@ pub unsafe trait ZalsaDatabase: Any {
     /// Clone the database.
     #[doc(hidden)]
     fn fork_db(&self) -> Box<dyn Database>;
+
+    /// Take an idle handle forked for a parallel operation, or clone the database if there is none.
+    #[doc(hidden)]
+    fn take_fork(&self) -> Box<dyn Database>;
+
+    /// Return a handle taken with [`ZalsaDatabase::take_fork`] to the idle handles of the storage,
+    /// or drop it if a new revision is pending.
+    #[doc(hidden)]
+    fn release_fork(self: Box<Self>);
 }
 
 pub fn views<Db: ?Sized + Database>(db: &Db) -> &Views {
@@ -142,20 +156,25 @@ pub struct Zalsa {
     /// Indices of ingredients that require reset when a new revision starts.
     ingredients_requiring_reset: AppendOnlyVec<IngredientIndex>,
 
+    /// Events that the database wants to receive, as returned by
+    /// [`Database::salsa_event_filter`]; queried on the first event.
+    event_filter: OnceLock<EventKindMask>,
+
     /// The runtime for this particular salsa database handle.
     /// Each handle gets its own runtime, but the runtimes have shared state between them.
     runtime: Runtime,
 }
 
 impl Zalsa {
-    pub(crate) fn new<Db: Database>() -> Self {
+    pub(crate) fn new<Db: Database>(durabilities: usize) -> Self {
         Self {
             views_of: Views::new::<Db>(),
             nonce: NONCE.nonce(),
             jar_map: Default::default(),
             ingredients_vec: AppendOnlyVec::new(),
             ingredients_requiring_reset: AppendOnlyVec::new(),
-            runtime: Runtime::default(),
+            event_filter: OnceLock::new(),
+            runtime: Runtime::new(durabilities),
             memo_ingredients: Default::default(),
         }
     }
@@ -253,17 +272,112 @@ impl Zalsa {
         self.runtime.last_changed_revision(durability)
     }
 
+    /// True if events of kind `kind` are to be passed to [`Database::salsa_event`].
+    #[inline]
+    pub(crate) fn should_report_event(&self, db: &dyn Database, kind: EventKindMask) -> bool {
+        self.event_filter
+            .get_or_init(|| db.salsa_event_filter())
+            .contains(kind)
+    }
+
+    /// Passes an event to [`Database::salsa_event`] if events of kind `mask` are to be reported.
+    /// `kind` creates the kind of the event, and is only called if the event is reported.
+    #[inline]
+    pub(crate) fn report_event(
+        &self,
+        db: &dyn Database,
+        mask: EventKindMask,
+        kind: impl Fn() -> EventKind,
+    ) {
+        if self.should_report_event(db, mask) {
+            db.salsa_event(&|| Event {
+                thread_id: crate::runtime::current_thread_id(),
+                kind: kind(),
+            });
+        }
+    }
+
+    pub(crate) fn set_watchdog(&mut self, timeout: Duration) {
+        self.runtime.set_watchdog(timeout)
+    }
+
+    pub(crate) fn set_max_query_depth(&mut self, max_depth: usize) {
+        self.runtime.set_max_query_depth(max_depth)
+    }
+
+    pub(crate) fn max_query_depth(&self) -> Option<usize> {
+        self.runtime.max_query_depth()
+    }
+
+    /// The highest durability level of the database (see [`Runtime::max_durability`]).
+    pub(crate) fn max_durability(&self) -> Durability {
+        self.runtime.max_durability()
+    }
+
+    pub(crate) fn set_overflow_stack_size(&mut self, stack_size: usize) {
+        self.runtime.set_overflow_stack_size(stack_size)
+    }
+
+    pub(crate) fn overflow_stack_size(&self) -> Option<usize> {
+        self.runtime.overflow_stack_size()
+    }
+
+    pub(crate) fn set_deterministic(&mut self) {
+        self.runtime.set_deterministic()
+    }
+
+    pub(crate) fn is_deterministic(&self) -> bool {
+        self.runtime.is_deterministic()
+    }
+
+    pub(crate) fn set_executor(&mut self, executor: Box<dyn Executor>) {
+        self.runtime.set_executor(executor)
+    }
+
+    pub(crate) fn executor(&self) -> &dyn Executor {
+        self.runtime.executor()
+    }
+
+    pub(crate) fn set_parallel_verification(&mut self, min_inputs: usize) {
+        self.runtime.set_parallel_verification(min_inputs)
+    }
+
+    pub(crate) fn parallel_verification(&self) -> Option<usize> {
+        self.runtime.parallel_verification()
+    }
+
+    pub(crate) fn wait_for_graph(&self) -> WaitForGraph {
+        self.runtime.wait_for_graph()
+    }
+
+    pub(crate) fn is_frozen(&self) -> bool {
+        self.frozen_at().is_some()
+    }
+
+    /// The revision in which the database has been frozen (see [`Runtime::frozen_at`]).
+    pub(crate) fn frozen_at(&self) -> Option<Revision> {
+        self.runtime.frozen_at()
+    }
+
+    pub(crate) fn freeze(&self) {
+        self.runtime.freeze()
+    }
+
     pub(crate) fn set_cancellation_flag(&self) {
         self.runtime.set_cancellation_flag()
     }
 
+    pub(crate) fn clear_cancellation_flag(&mut self) {
+        self.runtime.clear_cancellation_flag()
+    }
+
     /// Triggers a new revision. Invoked automatically when you call `zalsa_mut`
     /// and so doesn't need to be called otherwise.
     pub(crate) fn new_revision(&mut self) -> Revision {
         let new_revision = self.runtime.new_revision();
 
         for index in self.ingredients_requiring_reset.iter() {
-            self.ingredients_vec[index.as_usize()].reset_for_new_revision();
+            self.ingredients_vec[index.as_usize()].reset_for_new_revision(&mut self.runtime);
         }
 
         new_revision
@@ -3,24 +3,27 @@ use tracing::debug;
 
 use crate::accumulator::accumulated_map::AccumulatedMap;
 use crate::active_query::ActiveQuery;
-use crate::durability::Durability;
+use crate::durability::{Durability, LoweredBy};
 use crate::key::DatabaseKeyIndex;
 use crate::key::DependencyIndex;
+use crate::parallel::{TaskKey, TaskParent};
 use crate::runtime::StampedValue;
 use crate::table::PageIndex;
 use crate::table::Slot;
 use crate::table::Table;
-use crate::tracked_struct::{Disambiguator, Identity, IdentityHash};
+use crate::tracked_struct::{Identity, IdentityHash};
 use crate::zalsa::IngredientIndex;
 use crate::Accumulator;
 use crate::Cancelled;
 use crate::Cycle;
 use crate::Database;
-use crate::Event;
 use crate::EventKind;
+use crate::EventKindMask;
 use crate::Id;
+use crate::QueryStack;
 use crate::Revision;
-use std::cell::RefCell;
+use crate::StackOverflow;
+use std::cell::{Cell, RefCell};
 use std::sync::Arc;
 
 /// State that is specific to a single execution thread.
@@ -42,6 +45,30 @@ pub struct ZalsaLocal {
     /// Stores the most recent page for a given ingredient.
     /// This is thread-local to avoid contention.
     most_recent_pages: RefCell<FxHashMap<IngredientIndex, PageIndex>>,
+
+    /// The query stack captured when the most recent panic unwound
+    /// out of a query (see [`QueryStack::last_panic`]).
+    panic_query_stack: RefCell<Option<QueryStack>>,
+
+    /// Length of the query stack when this handle started to continue it
+    /// (see [`crate::stack_overflow::continue_on_new_thread`]). The query depth
+    /// limit applies to the queries above this base.
+    query_stack_base: Cell<usize>,
+
+    /// Set when `panic_query_stack` is captured, reset when the next query is pushed, when a
+    /// query completes normally, or when the panic unwinds out of the outermost query.
+    /// Avoids capturing the stack again as the panic unwinds through the outer queries.
+    /// Also set when salsa unwinds with a cycle or a cancellation (see [`Self::throw_cycle`]),
+    /// for which the stack is not captured.
+    unwinding: Cell<bool>,
+
+    /// Set on handles that verify dependencies speculatively (see [`crate::parallel::prefetch`]).
+    /// Such handles never block on queries claimed by other threads and never execute queries.
+    speculative: Cell<bool>,
+
+    /// The idle speculative handles of the verification walk in progress on this handle, if any,
+    /// reused by each prefetch of the walk (see [`crate::parallel::PrefetchWalk`]).
+    prefetch_handles: RefCell<Option<Vec<Box<dyn Database>>>>,
 }
 
 impl ZalsaLocal {
@@ -49,6 +76,51 @@ impl ZalsaLocal {
         ZalsaLocal {
             query_stack: RefCell::new(Some(vec![])),
             most_recent_pages: RefCell::new(FxHashMap::default()),
+            query_stack_base: Cell::new(0),
+            panic_query_stack: RefCell::new(None),
+            unwinding: Cell::new(false),
+            speculative: Cell::new(false),
+            prefetch_handles: RefCell::new(None),
+        }
+    }
+
+    pub(crate) fn set_speculative(&self) {
+        self.speculative.set(true);
+    }
+
+    pub(crate) fn is_speculative(&self) -> bool {
+        self.speculative.get()
+    }
+
+    /// Starts a verification walk that keeps speculative handles, unless one is in progress.
+    /// Returns true if it was started.
+    pub(crate) fn start_prefetch_walk(&self) -> bool {
+        let mut handles = self.prefetch_handles.borrow_mut();
+        if handles.is_some() {
+            return false;
+        }
+        *handles = Some(vec![]);
+        true
+    }
+
+    /// Ends the verification walk, dropping its speculative handles.
+    pub(crate) fn end_prefetch_walk(&self) {
+        drop(self.prefetch_handles.take());
+    }
+
+    /// Takes the idle speculative handles of the walk in progress.
+    pub(crate) fn take_prefetch_handles(&self) -> Vec<Box<dyn Database>> {
+        self.prefetch_handles
+            .borrow_mut()
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Returns idle speculative handles to the walk in progress, or drops them if there is none.
+    pub(crate) fn return_prefetch_handles(&self, idle: Vec<Box<dyn Database>>) {
+        if let Some(handles) = self.prefetch_handles.borrow_mut().as_mut() {
+            handles.extend(idle);
         }
     }
 
@@ -85,11 +157,63 @@ impl ZalsaLocal {
         }
     }
 
+    /// Allocate new ids in `table` for the given ingredient, one for each of `values`.
+    /// Like [`Self::allocate`], but fills pages in bulk, so that the slots for
+    /// consecutive values are contiguous wherever possible.
+    pub(crate) fn allocate_batch<T: Slot>(
+        &self,
+        table: &Table,
+        ingredient: IngredientIndex,
+        values: impl IntoIterator<Item = T>,
+    ) -> Vec<Id> {
+        // Collect the values first: producing them may run arbitrary code,
+        // which must not happen while a page is locked for allocation.
+        let mut values = values.into_iter().collect::<Vec<T>>().into_iter();
+        let mut ids = Vec::with_capacity(values.len());
+
+        // Find the most recent page, pushing a page if needed
+        let mut page = *self
+            .most_recent_pages
+            .borrow_mut()
+            .entry(ingredient)
+            .or_insert_with(|| table.push_page::<T>(ingredient));
+
+        loop {
+            // Fill up that page as far as possible
+            table
+                .page::<T>(page)
+                .allocate_batch(page, &mut values, &mut ids);
+
+            // If there are values left, the page is full: create a new page and continue
+            if values.len() == 0 {
+                return ids;
+            }
+            page = table.push_page::<T>(ingredient);
+            self.most_recent_pages.borrow_mut().insert(ingredient, page);
+        }
+    }
+
+    /// Pushes a new active query, whose durability starts out as `max_durability`
+    /// (see [`Zalsa::max_durability`]). If that exceeds `max_depth` (see
+    /// [`Storage::with_max_query_depth`](`crate::Storage::with_max_query_depth`)),
+    /// unwinds with [`StackOverflow`] instead.
     #[inline]
-    pub(crate) fn push_query(&self, database_key_index: DatabaseKeyIndex) -> ActiveQueryGuard<'_> {
+    pub(crate) fn push_query(
+        &self,
+        database_key_index: DatabaseKeyIndex,
+        max_depth: Option<usize>,
+        max_durability: Durability,
+    ) -> ActiveQueryGuard<'_> {
+        if self.at_query_depth_limit(max_depth) {
+            let mut frames = self.active_query_keys();
+            frames.push(database_key_index);
+            StackOverflow::new(max_depth.unwrap(), QueryStack::from_frames(frames)).throw();
+        }
+
+        self.unwinding.set(false);
         let mut query_stack = self.query_stack.borrow_mut();
         let query_stack = query_stack.as_mut().expect("local stack taken");
-        query_stack.push(ActiveQuery::new(database_key_index));
+        query_stack.push(ActiveQuery::new(database_key_index, max_durability));
         ActiveQueryGuard {
             local_state: self,
             database_key_index,
@@ -97,6 +221,82 @@ impl ZalsaLocal {
         }
     }
 
+    /// True if pushing another query would exceed `max_depth`.
+    #[inline]
+    pub(crate) fn at_query_depth_limit(&self, max_depth: Option<usize>) -> bool {
+        match max_depth {
+            Some(max_depth) => {
+                self.with_query_stack(|stack| stack.len()) - self.query_stack_base.get()
+                    >= max_depth
+            }
+            None => false,
+        }
+    }
+
+    /// Continues `stack`, taken from another handle with [`Self::take_query_stack`], on this
+    /// (fresh) handle. The query depth limit applies to the queries pushed on top of it.
+    pub(crate) fn continue_query_stack(&self, stack: Vec<ActiveQuery>) {
+        self.query_stack_base.set(stack.len());
+        let old_stack = self.query_stack.replace(Some(stack));
+        assert!(
+            old_stack.is_some_and(|stack| stack.is_empty()),
+            "query stack not empty"
+        );
+    }
+
+    /// The keys of the active queries, outermost first.
+    /// Empty if the query stack is not available (e.g. while blocked on another thread).
+    pub(crate) fn active_query_keys(&self) -> Vec<DatabaseKeyIndex> {
+        match self.query_stack.try_borrow() {
+            Ok(stack) => stack
+                .iter()
+                .flatten()
+                .map(|query| query.database_key_index)
+                .collect(),
+            Err(_) => vec![],
+        }
+    }
+
+    /// Invoked when a panic unwinds out of the innermost active query:
+    /// captures the query stack, unless it was already captured for this panic.
+    fn capture_panic_query_stack(&self) {
+        if !self.unwinding.replace(true) {
+            let stack = QueryStack::from_frames(self.active_query_keys());
+            *self.panic_query_stack.borrow_mut() = Some(stack);
+        }
+    }
+
+    /// Invoked when a panic unwinds out of the query pushed at `push_len`:
+    /// if that is the outermost query, the next panic is a new one.
+    fn unwound_out_of(&self, push_len: usize) {
+        if push_len <= self.query_stack_base.get() + 1 {
+            self.unwinding.set(false);
+        }
+    }
+
+    /// Unwinds with `cycle`. Unlike a panic, this does not capture the query stack.
+    pub(crate) fn throw_cycle(&self, cycle: Cycle) -> ! {
+        self.unwinding.set(true);
+        cycle.throw()
+    }
+
+    /// Unwinds with `cancelled`. Like [`Self::throw_cycle`], this does not capture the query stack.
+    pub(crate) fn throw_cancelled(&self, cancelled: Cancelled) -> ! {
+        self.unwinding.set(true);
+        cancelled.throw()
+    }
+
+    pub(crate) fn last_panic_query_stack(&self) -> Option<QueryStack> {
+        self.panic_query_stack.borrow().clone()
+    }
+
+    /// Takes over the stack captured for a panic that unwound out of the queries of another
+    /// handle, and that keeps unwinding through the queries of this one.
+    pub(crate) fn resume_panic_query_stack(&self, stack: QueryStack) {
+        *self.panic_query_stack.borrow_mut() = Some(stack);
+        self.unwinding.set(true);
+    }
+
     fn with_query_stack<R>(&self, c: impl FnOnce(&mut Vec<ActiveQuery>) -> R) -> R {
         c(self
             .query_stack
@@ -201,7 +401,7 @@ impl ZalsaLocal {
                 // stack frames, so they will just read the fallback value
                 // from `Ci+1` and continue on their merry way.
                 if let Some(cycle) = &top_query.cycle {
-                    cycle.clone().throw()
+                    self.throw_cycle(cycle.clone())
                 }
             }
         })
@@ -259,9 +459,9 @@ impl ZalsaLocal {
     /// * Returns a tuple of:
     ///   * the id of the current query
     ///   * the current dependencies (durability, changed_at) of current query
-    ///   * the disambiguator index
+    ///   * the identity of the struct, with its disambiguator index
     #[track_caller]
-    pub(crate) fn disambiguate(&self, key: IdentityHash) -> (StampedValue<()>, Disambiguator) {
+    pub(crate) fn disambiguate(&self, key: IdentityHash) -> (StampedValue<()>, Identity) {
         assert!(
             self.query_in_progress(),
             "cannot create a tracked struct disambiguator outside of a tracked function"
@@ -269,14 +469,14 @@ impl ZalsaLocal {
 
         self.with_query_stack(|stack| {
             let top_query = stack.last_mut().unwrap();
-            let disambiguator = top_query.disambiguate(key);
+            let identity = top_query.disambiguate(key);
             (
                 StampedValue {
                     value: (),
                     durability: top_query.durability,
                     changed_at: top_query.changed_at,
                 },
-                disambiguator,
+                identity,
             )
         })
     }
@@ -290,7 +490,7 @@ impl ZalsaLocal {
 
         self.with_query_stack(|stack| {
             let top_query = stack.last().unwrap();
-            top_query.tracked_struct_ids.get(identity).copied()
+            top_query.tracked_struct_id(identity)
         })
     }
 
@@ -302,7 +502,7 @@ impl ZalsaLocal {
         );
         self.with_query_stack(|stack| {
             let top_query = stack.last_mut().unwrap();
-            let old_id = top_query.tracked_struct_ids.insert(identity, id);
+            let old_id = top_query.store_tracked_struct_id(identity, id);
             assert!(
                 old_id.is_none(),
                 "overwrote a previous id for `{identity:?}`"
@@ -310,6 +510,32 @@ impl ZalsaLocal {
         })
     }
 
+    /// Prepares the active query, if any, to run tasks in parallel (see [`crate::parallel`]).
+    /// Returns the active query, its current durability/changed-at information, its tracked
+    /// struct identities, to be shared with the tasks, and the key of the operation.
+    pub(crate) fn start_tasks(&self) -> Option<TaskParent> {
+        let (database_key_index, stamp) = self.active_query()?;
+        let (operation, identities) =
+            self.with_query_stack(|stack| stack.last_mut().unwrap().start_operation());
+        Some(TaskParent {
+            database_key_index,
+            stamp,
+            identities,
+            operation,
+        })
+    }
+
+    /// Adds the dependencies and outputs of the `tasks` that the active query ran in parallel
+    /// to the active query, one task after the other, in the order of the iterator.
+    pub(crate) fn complete_tasks(&self, tasks: impl IntoIterator<Item = ActiveQuery>) {
+        self.with_query_stack(|stack| {
+            let top_query = stack.last_mut().unwrap();
+            for task in tasks {
+                top_query.add_task(task);
+            }
+        })
+    }
+
     /// Starts unwinding the stack if the current revision is cancelled.
     ///
     /// This method can be called by query implementations that perform
@@ -323,13 +549,20 @@ impl ZalsaLocal {
     /// `salsa_event` is emitted when this method is called, so that should be
     /// used instead.
     pub(crate) fn unwind_if_revision_cancelled(&self, db: &dyn Database) {
-        let thread_id = std::thread::current().id();
-        db.salsa_event(&|| Event {
-            thread_id,
+        // A frozen database cannot be written to, so there is nothing to check for.
+        if db.zalsa().is_frozen() {
+            return;
+        }
+        self.unwind_if_unfrozen_revision_cancelled(db);
+    }
 
-            kind: EventKind::WillCheckCancellation,
-        });
+    /// Like [`Self::unwind_if_revision_cancelled`], for callers that have already
+    /// checked that the database is not frozen.
+    pub(crate) fn unwind_if_unfrozen_revision_cancelled(&self, db: &dyn Database) {
         let zalsa = db.zalsa();
+        zalsa.report_event(db, EventKindMask::WILL_CHECK_CANCELLATION, || {
+            EventKind::WillCheckCancellation
+        });
         if zalsa.load_cancellation_flag() {
             self.unwind_cancelled(zalsa.current_revision());
         }
@@ -338,7 +571,7 @@ impl ZalsaLocal {
     #[cold]
     pub(crate) fn unwind_cancelled(&self, current_revision: Revision) {
         self.report_untracked_read(current_revision);
-        Cancelled::PendingWrite.throw();
+        self.throw_cancelled(Cancelled::PendingWrite);
     }
 }
 
@@ -354,6 +587,9 @@ pub(crate) struct QueryRevisions {
     /// Minimum durability of the inputs to this query.
     pub(crate) durability: Durability,
 
+    /// The input that lowered the durability to `durability`, if known.
+    pub(crate) durability_lowered_by: LoweredBy,
+
     /// How was this query computed?
     pub(crate) origin: QueryOrigin,
 
@@ -542,9 +778,23 @@ impl ActiveQueryGuard<'_> {
         })
     }
 
+    /// Initializes `task`, run in parallel by another query (see [`ZalsaLocal::start_tasks`]):
+    /// it starts with the dependencies of that query so far, and shares its tracked struct identities.
+    pub(crate) fn seed_task(&self, parent: &TaskParent, task: TaskKey) {
+        self.local_state.with_query_stack(|stack| {
+            assert_eq!(stack.len(), self.push_len);
+            let frame = stack.last_mut().unwrap();
+            frame.durability = parent.stamp.durability;
+            frame.changed_at = parent.stamp.changed_at;
+            frame.use_shared_identities(task, parent.identities.clone());
+        })
+    }
+
     /// Invoked when the query has successfully completed execution.
     pub(crate) fn complete(self) -> ActiveQuery {
         let query = self.pop_helper();
+        // If a panic unwound through the queries this one called, this query caught it.
+        self.local_state.unwinding.set(false);
         std::mem::forget(self);
         query
     }
@@ -573,6 +823,10 @@ impl ActiveQueryGuard<'_> {
 
 impl Drop for ActiveQueryGuard<'_> {
     fn drop(&mut self) {
+        if std::thread::panicking() {
+            self.local_state.capture_panic_query_stack();
+        }
         self.pop_helper();
+        self.local_state.unwound_out_of(self.push_len);
     }
 }
@@ -0,0 +1,95 @@
+//! Test inspecting the active query stack from within tracked functions.
+
+mod common;
+use common::LogDatabase;
+
+use expect_test::expect;
+use salsa::{Database, Setter};
+use test_log::test;
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::interned]
+struct Depth<'db> {
+    value: u32,
+}
+
+/// Recurses until the stack contains `limit` frames of `recurse`.
+#[salsa::tracked]
+fn recurse<'db>(db: &'db dyn Database, input: MyInput, depth: Depth<'db>) -> u32 {
+    let me = db.current_query().unwrap();
+    let frames = db
+        .active_query_stack()
+        .iter()
+        .filter(|key| key.ingredient_index() == me.ingredient_index())
+        .count();
+    if frames as u32 >= input.field(db) {
+        depth.value(db)
+    } else {
+        recurse(db, input, Depth::new(db, depth.value(db) + 1))
+    }
+}
+
+#[salsa::tracked]
+fn outer(db: &dyn Database, input: MyInput) -> String {
+    let _ = input.field(db);
+    inner(db, input)
+}
+
+#[salsa::tracked]
+fn inner(db: &dyn Database, _input: MyInput) -> String {
+    format!("{:?}", db.active_query_stack())
+}
+
+#[test]
+fn stack_outside_of_tracked_fn() {
+    let db = salsa::DatabaseImpl::new();
+    assert!(db.active_query_stack().is_empty());
+    assert_eq!(db.current_query(), None);
+}
+
+#[test]
+fn stack_inside_of_tracked_fn() {
+    let db = salsa::DatabaseImpl::new();
+    let input = MyInput::new(&db, 0);
+    expect!["[outer(Id(0)), inner(Id(0))]"].assert_eq(&outer(&db, input));
+}
+
+#[test]
+fn recursion_limit() {
+    let mut db = salsa::DatabaseImpl::new();
+    let input = MyInput::new(&db, 3);
+    let depth = Depth::new(&db, 0);
+    assert_eq!(recurse(&db, input, depth), 2);
+
+    input.set_field(&mut db).to(5);
+    let depth = Depth::new(&db, 0);
+    assert_eq!(recurse(&db, input, depth), 4);
+}
+
+#[test]
+fn stack_does_not_record_dependency() {
+    #[salsa::tracked]
+    fn stack_len(db: &dyn LogDatabase, input: MyInput) -> usize {
+        db.push_log("stack_len".to_string());
+        let _ = input.field(db);
+        db.active_query_stack().len()
+    }
+
+    let mut db = common::LoggerDatabase::default();
+    let input = MyInput::new(&db, 0);
+    let other = MyInput::new(&db, 1);
+    assert_eq!(stack_len(&db, input), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "stack_len",
+        ]"#]]);
+
+    // The memo is re-used in a new revision, so reading the stack was not an untracked read.
+    other.set_field(&mut db).to(2);
+    assert_eq!(stack_len(&db, input), 1);
+    db.assert_logs(expect!["[]"]);
+}
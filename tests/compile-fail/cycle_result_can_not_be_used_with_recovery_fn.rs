@@ -0,0 +1,22 @@
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked(recovery_fn = recover, cycle_result)]
+fn cycle_result_can_not_be_used_with_recovery_fn(
+    db: &dyn salsa::Database,
+    input: MyInput,
+) -> Result<u32, salsa::Cycle> {
+    Ok(input.field(db))
+}
+
+fn recover(
+    _db: &dyn salsa::Database,
+    cycle: &salsa::Cycle,
+    _input: MyInput,
+) -> Result<u32, salsa::Cycle> {
+    Err(cycle.clone())
+}
+
+fn main() {}
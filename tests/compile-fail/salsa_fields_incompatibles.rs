@@ -10,4 +10,29 @@ struct InputWithBannedName2 {
     new: u32,
 }
 
+// Field name that clashes with the generated `update` method
+#[salsa::input]
+struct InputWithBannedName3 {
+    update: u32,
+}
+
+// Field name that clashes with the `apply` method of the updater
+#[salsa::input]
+struct InputWithBannedName4 {
+    apply: u32,
+}
+
+// Getter name that clashes with the generated `update` method
+#[salsa::input]
+struct InputWithBannedName5 {
+    #[get(update)]
+    field: u32,
+}
+
+// Field name that clashes with the generated `delete` method
+#[salsa::input]
+struct InputWithBannedName6 {
+    delete: u32,
+}
+
 fn main() {}
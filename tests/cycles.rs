@@ -44,6 +44,11 @@ use salsa::Durability;
 // | Intra  | Fallback | Old      | Tracked   | direct   | cycle_disappears_durability |
 // | Intra  | Mixed    | N/A      | Tracked   | direct   | cycle_mixed_1 |
 // | Intra  | Mixed    | N/A      | Tracked   | direct   | cycle_mixed_2 |
+// | Intra  | Result   | N/A      | Tracked   | direct   | cycle_result |
+// | Intra  | Result   | N/A      | Tracked   | indirect | cycle_result_indirect |
+// | Intra  | Context  | N/A      | Tracked   | direct   | cycle_context |
+// | Intra  | Fallback | N/A      | Tracked   | direct   | cycle_through_specified_default |
+// | Intra  | Fallback | N/A      | Tracked   | indirect | cycle_through_specified_value |
 // | Cross  | Panic    | N/A      | Tracked   | both     | parallel/parallel_cycle_none_recover.rs |
 // | Cross  | Fallback | N/A      | Tracked   | both     | parallel/parallel_cycle_one_recover.rs |
 // | Cross  | Fallback | N/A      | Tracked   | both     | parallel/parallel_cycle_mid_recover.rs |
@@ -143,6 +148,97 @@ fn cycle_c(db: &dyn Db, abc: ABC) -> Result<(), Error> {
     abc.c(db).invoke(db, abc)
 }
 
+#[salsa::tracked(recovery_fn=recover_context)]
+fn context_a(db: &dyn Db, input: MyInput) -> String {
+    context_b(db, input)
+}
+
+#[salsa::tracked(recovery_fn=recover_context)]
+fn context_b(db: &dyn Db, input: MyInput) -> String {
+    context_c(db, input)
+}
+
+#[salsa::tracked]
+fn context_c(db: &dyn Db, input: MyInput) -> String {
+    context_a(db, input)
+}
+
+fn recover_context(_db: &dyn Db, context: &salsa::CycleContext, _input: MyInput) -> String {
+    format!(
+        "{:?} at position {} (head: {:?}, is head: {}), participants: {:?}",
+        context.key(),
+        context.position(),
+        context.head(),
+        context.is_head(),
+        context.participants().collect::<Vec<_>>(),
+    )
+}
+
+#[salsa::input]
+struct Limit {
+    value: u32,
+}
+
+#[salsa::tracked]
+struct Node<'db> {
+    limit: Limit,
+}
+
+/// Creates a node and specifies its `specified_default` from the current
+/// value, which loops back into this query.
+#[salsa::tracked]
+fn specify_from_default(db: &dyn Db, limit: Limit) -> Node<'_> {
+    let node = Node::new(db, limit);
+    let value = specified_default(db, node);
+    specified_default::specify(db, node, value + limit.value(db));
+    node
+}
+
+#[salsa::tracked(specify, recovery_fn=recover_specified_default)]
+fn specified_default<'db>(db: &'db dyn Db, node: Node<'db>) -> u32 {
+    specified_default(db, specify_from_default(db, node.limit(db)))
+}
+
+fn recover_specified_default<'db>(
+    _db: &'db dyn Db,
+    _cycle: &salsa::Cycle,
+    _node: Node<'db>,
+) -> u32 {
+    1000
+}
+
+/// Creates a node, specifies its `specified_value`, and then reads
+/// `read_specified`, which loops back into this query for large values.
+#[salsa::tracked(recovery_fn=recover_specify_then_read)]
+fn specify_then_read(db: &dyn Db, limit: Limit) -> u32 {
+    let node = Node::new(db, limit);
+    specified_value::specify(db, node, limit.value(db));
+    read_specified(db, node)
+}
+
+fn recover_specify_then_read(_db: &dyn Db, _cycle: &salsa::Cycle, _limit: Limit) -> u32 {
+    1000
+}
+
+#[salsa::tracked(recovery_fn=recover_read_specified)]
+fn read_specified<'db>(db: &'db dyn Db, node: Node<'db>) -> u32 {
+    let value = specified_value(db, node);
+    if value > 10 {
+        specify_then_read(db, node.limit(db))
+    } else {
+        value
+    }
+}
+
+fn recover_read_specified<'db>(_db: &'db dyn Db, _cycle: &salsa::Cycle, _node: Node<'db>) -> u32 {
+    2000
+}
+
+#[salsa::tracked(specify)]
+fn specified_value<'db>(_db: &'db dyn Db, _node: Node<'db>) -> u32 {
+    panic!("value was not specified")
+}
+
 #[track_caller]
 fn extract_cycle(f: impl FnOnce() + UnwindSafe) -> salsa::Cycle {
     let v = std::panic::catch_unwind(f);
@@ -169,6 +265,44 @@ fn cycle_memoized() {
     })
 }
 
+#[test]
+fn cycle_render() {
+    salsa::DatabaseImpl::new().attach(|db| {
+        let input = MyInput::new(db);
+        let cycle = extract_cycle(|| memoized_b(db, input));
+        assert_eq!(
+            cycle.render(db),
+            "memoized_a(Id(0)) -> memoized_b(Id(0)) -> memoized_a(Id(0))"
+        );
+        assert!(cycle.edges().all(|edge| !edge.crosses_threads()));
+        let edges: Vec<_> = cycle.edges().map(|edge| (edge.from, edge.to)).collect();
+        let expected = expect![[r#"
+            [
+                (
+                    memoized_a(Id(0)),
+                    memoized_b(Id(0)),
+                ),
+                (
+                    memoized_b(Id(0)),
+                    memoized_a(Id(0)),
+                ),
+            ]
+        "#]];
+        expected.assert_debug_eq(&edges);
+    })
+}
+
+#[test]
+fn cycle_context() {
+    salsa::DatabaseImpl::new().attach(|db| {
+        let input = MyInput::new(db);
+        let expected = expect!["context_a(Id(0)) at position 0 (head: context_a(Id(0)), is head: true), participants: [(context_a(Id(0)), true), (context_b(Id(0)), true), (context_c(Id(0)), false)]"];
+        expected.assert_eq(&context_a(db, input));
+        let expected = expect!["context_b(Id(0)) at position 1 (head: context_a(Id(0)), is head: false), participants: [(context_a(Id(0)), true), (context_b(Id(0)), true), (context_c(Id(0)), false)]"];
+        expected.assert_eq(&context_b(db, input));
+    })
+}
+
 #[test]
 fn cycle_volatile() {
     salsa::DatabaseImpl::new().attach(|db| {
@@ -434,3 +568,93 @@ fn cycle_recovery_set_but_not_participating() {
         expected.assert_debug_eq(&r.all_participants(db));
     })
 }
+
+#[salsa::tracked(cycle_result)]
+fn result_a(db: &dyn Db, input: MyInput) -> Result<u32, salsa::Cycle> {
+    result_b(db, input)
+}
+
+#[salsa::tracked(cycle_result)]
+fn result_b(db: &dyn Db, input: MyInput) -> Result<u32, salsa::Cycle> {
+    Ok(result_a(db, input)? + 1)
+}
+
+#[salsa::tracked]
+fn result_outside(db: &dyn Db, input: MyInput) -> Result<u32, String> {
+    result_a(db, input).map_err(|cycle| format!("{:?}", cycle.all_participants(db)))
+}
+
+#[test]
+fn cycle_result() {
+    let db = salsa::DatabaseImpl::new();
+    let input = MyInput::new(&db);
+
+    // Every participant returns the cycle as an error, without panicking.
+    let cycle = result_a(&db, input).unwrap_err();
+    assert_eq!(result_b(&db, input), Err(cycle.clone()));
+    db.attach(|db| {
+        expect![[r#"
+            [
+                result_a(Id(0)),
+                result_b(Id(0)),
+            ]
+        "#]]
+        .assert_debug_eq(&cycle.all_participants(db));
+    });
+}
+
+#[test]
+fn cycle_result_indirect() {
+    let db = salsa::DatabaseImpl::new();
+    let input = MyInput::new(&db);
+
+    // A query outside of the cycle sees the error like any other value.
+    expect![[r#"
+        Err(
+            "[result_a(Id(0)), result_b(Id(0))]",
+        )
+    "#]]
+    .assert_debug_eq(&result_outside(&db, input));
+}
+
+#[test]
+fn cycle_through_specified_default() {
+    // specify_from_default --> specified_default(node) --+
+    //          ^                                         |
+    //          +-----------------------------------------+
+    let mut db = salsa::DatabaseImpl::new();
+    let limit = Limit::new(&db, 1);
+
+    let node = specify_from_default(&db, limit);
+    assert_eq!(specified_default(&db, node), 1001);
+    assert_eq!(node.limit(&db).value(&db), 1);
+
+    // The recovered value did not take over the outputs of `specify_from_default`,
+    // so re-executing it does not delete the node.
+    limit.set_value(&mut db).to(2);
+    let node = specify_from_default(&db, limit);
+    assert_eq!(specified_default(&db, node), 1002);
+    assert_eq!(node.limit(&db).value(&db), 2);
+}
+
+#[test]
+fn cycle_through_specified_value() {
+    // specify_then_read --> read_specified(node) --> specified_value(node)
+    //          ^                    |
+    //          +--------------------+ (if the specified value is greater than 10)
+    //
+    // Both participants recover: only `specify_then_read` keeps the node and the specified
+    // value as its outputs, so re-executing `read_specified` does not delete them.
+    let mut db = salsa::DatabaseImpl::new();
+    let limit = Limit::new(&db, 20);
+    assert_eq!(specify_then_read(&db, limit), 1000);
+
+    limit.set_value(&mut db).to(5);
+    assert_eq!(specify_then_read(&db, limit), 5);
+
+    limit.set_value(&mut db).to(50);
+    assert_eq!(specify_then_read(&db, limit), 1000);
+
+    db.synthetic_write(Durability::LOW);
+    assert_eq!(specify_then_read(&db, limit), 1000);
+}
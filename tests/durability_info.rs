@@ -0,0 +1,146 @@
+//! Test `durability_info` and the `expect_durability` option on tracked functions.
+
+use salsa::{Database, Durability, DurabilityInfo, Setter};
+use test_log::test;
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn read_one(db: &dyn salsa::Database, input: MyInput) -> u32 {
+    input.field(db)
+}
+
+#[salsa::tracked]
+fn read_two(db: &dyn salsa::Database, high: MyInput, low: MyInput) -> u32 {
+    read_one(db, high) + low.field(db)
+}
+
+#[salsa::tracked]
+fn read_nothing(_db: &dyn salsa::Database, _input: MyInput) -> u32 {
+    0
+}
+
+#[salsa::tracked(expect_durability = HIGH)]
+fn expect_high(db: &dyn salsa::Database, input: MyInput) -> u32 {
+    read_one(db, input)
+}
+
+#[test]
+fn durability_info() {
+    let db = salsa::DatabaseImpl::new();
+    let high = MyInput::builder(1).durability(Durability::HIGH).new(&db);
+    let low = MyInput::new(&db, 2);
+
+    assert_eq!(read_one::durability_info(&db, high), None);
+    assert_eq!(read_two(&db, high, low), 3);
+
+    let DurabilityInfo {
+        durability,
+        lowered_by,
+    } = read_one::durability_info(&db, high).unwrap();
+    assert_eq!(durability, Durability::HIGH);
+    assert_lowered_by(&db, lowered_by, "Some(field(Id(0)))");
+
+    let DurabilityInfo {
+        durability,
+        lowered_by,
+    } = read_two::durability_info(&db, high, low).unwrap();
+    assert_eq!(durability, Durability::LOW);
+    assert_lowered_by(&db, lowered_by, "Some(field(Id(1)))");
+}
+
+/// The input that lowered the durability is only recorded in debug builds.
+fn assert_lowered_by(
+    db: &salsa::DatabaseImpl,
+    lowered_by: Option<salsa::DependencyIndex>,
+    expected: &str,
+) {
+    if cfg!(debug_assertions) {
+        db.attach(|_| assert_eq!(format!("{lowered_by:?}"), expected));
+    } else {
+        assert_eq!(lowered_by, None);
+    }
+}
+
+#[test]
+fn durability_info_without_reads() {
+    let db = salsa::DatabaseImpl::new();
+    let input = MyInput::new(&db, 1);
+    assert_eq!(read_nothing(&db, input), 0);
+
+    // A function without reads has the highest durability level of the database.
+    assert_eq!(
+        read_nothing::durability_info(&db, input),
+        Some(DurabilityInfo {
+            durability: Durability::HIGH,
+            lowered_by: None,
+        })
+    );
+}
+
+#[test]
+fn expect_durability_met() {
+    let db = salsa::DatabaseImpl::new();
+    let input = MyInput::builder(1).durability(Durability::HIGH).new(&db);
+    assert_eq!(expect_high(&db, input), 1);
+}
+
+#[test]
+#[cfg_attr(
+    debug_assertions,
+    should_panic(
+        expected = "expect_high(Id(0)): expected durability Durability(2), but computed durability Durability(0) (lowered by Some(read_one(Id(0))))"
+    )
+)]
+fn expect_durability_violated() {
+    let mut db = salsa::DatabaseImpl::new();
+    let input = MyInput::builder(1).durability(Durability::HIGH).new(&db);
+    assert_eq!(expect_high(&db, input), 1);
+
+    // Setting the input with low durability lowers the durability of `read_one`,
+    // and hence of `expect_high`.
+    input
+        .set_field(&mut db)
+        .with_durability(Durability::LOW)
+        .to(2);
+    assert_eq!(expect_high(&db, input), 2);
+}
+
+#[salsa::tracked(recovery_fn = recover_cycle)]
+fn cycle_high(db: &dyn salsa::Database, high: MyInput, low: MyInput) -> u32 {
+    high.field(db) + cycle_low(db, high, low)
+}
+
+#[salsa::tracked(recovery_fn = recover_cycle)]
+fn cycle_low(db: &dyn salsa::Database, high: MyInput, low: MyInput) -> u32 {
+    low.field(db) + cycle_high(db, high, low)
+}
+
+fn recover_cycle(
+    _db: &dyn salsa::Database,
+    _cycle: &salsa::Cycle,
+    _high: MyInput,
+    _low: MyInput,
+) -> u32 {
+    0
+}
+
+#[test]
+fn durability_info_after_cycle_recovery() {
+    let db = salsa::DatabaseImpl::new();
+    let high = MyInput::builder(1).durability(Durability::HIGH).new(&db);
+    let low = MyInput::new(&db, 2);
+    assert_eq!(cycle_high(&db, high, low), 0);
+
+    // Both participants take the inputs of the whole cycle, including the input
+    // that lowered the durability, which `cycle_high` did not read itself.
+    let DurabilityInfo {
+        durability,
+        lowered_by,
+    } = cycle_high::durability_info(&db, high, low).unwrap();
+    assert_eq!(durability, Durability::LOW);
+    assert_lowered_by(&db, lowered_by, "Some(field(Id(1)))");
+}
@@ -0,0 +1,119 @@
+//! Test that databases can be configured with more than three durability levels,
+//! and that changes at one level do not force revalidation of queries at higher levels.
+
+mod common;
+use common::{HasLogger, LogDatabase, Logger};
+
+use expect_test::expect;
+use salsa::{Durability, Setter, Storage};
+use test_log::test;
+
+const WORKSPACE: Durability = Durability::new(0);
+const TOOLCHAIN: Durability = Durability::new(2);
+const GENERATED: Durability = Durability::new(3);
+
+#[salsa::db]
+#[derive(Clone)]
+struct Database {
+    storage: Storage<Self>,
+    logger: Logger,
+}
+
+impl Database {
+    fn with_durabilities(levels: usize) -> Self {
+        Self {
+            storage: Storage::with_durabilities(levels),
+            logger: Logger::default(),
+        }
+    }
+}
+
+#[salsa::db]
+impl salsa::Database for Database {
+    fn salsa_event(&self, event: &dyn Fn() -> salsa::Event) {
+        let event = event();
+        match event.kind {
+            salsa::EventKind::WillExecute { .. }
+            | salsa::EventKind::DidValidateMemoizedValue { .. } => {
+                self.push_log(format!("salsa_event({:?})", event.kind));
+            }
+            _ => {}
+        }
+    }
+}
+
+impl HasLogger for Database {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn inner(db: &dyn salsa::Database, input: MyInput) -> u32 {
+    input.field(db)
+}
+
+#[salsa::tracked]
+fn outer(db: &dyn salsa::Database, input: MyInput) -> u32 {
+    inner(db, input) * 2
+}
+
+/// Creates a `GENERATED` and a `TOOLCHAIN` input, computes `outer` on the former,
+/// then changes the latter.
+fn change_lower_level(db: &mut Database) {
+    let generated = MyInput::builder(1).durability(GENERATED).new(db);
+    let toolchain = MyInput::builder(1).durability(TOOLCHAIN).new(db);
+    assert_eq!(outer(db, generated), 2);
+    db.assert_logs_len(2);
+
+    toolchain.set_field(db).with_durability(TOOLCHAIN).to(2);
+    assert_eq!(outer(db, generated), 2);
+}
+
+#[test]
+fn five_levels() {
+    let mut db = Database::with_durabilities(5);
+    change_lower_level(&mut db);
+
+    // `outer` only depends on `GENERATED` inputs, which did not change,
+    // so it is validated without looking at `inner`.
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(DidValidateMemoizedValue { database_key: outer(Id(0)) })",
+        ]"#]]);
+}
+
+#[test]
+fn three_levels() {
+    let mut db = Database::with_durabilities(3);
+    change_lower_level(&mut db);
+
+    // `GENERATED` is treated like `TOOLCHAIN` (the highest configured level),
+    // so `outer` has to validate its dependencies.
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(DidValidateMemoizedValue { database_key: inner(Id(0)) })",
+            "salsa_event(DidValidateMemoizedValue { database_key: outer(Id(0)) })",
+        ]"#]]);
+}
+
+#[test]
+fn changes_at_higher_levels_are_seen() {
+    let mut db = Database::with_durabilities(5);
+    let input = MyInput::builder(1).durability(WORKSPACE).new(&db);
+    assert_eq!(outer(&db, input), 2);
+
+    input.set_field(&mut db).with_durability(GENERATED).to(2);
+    assert_eq!(outer(&db, input), 4);
+}
+
+#[test]
+#[should_panic(expected = "the number of durability levels must be between 1 and 256")]
+fn zero_levels() {
+    Database::with_durabilities(0);
+}
@@ -0,0 +1,85 @@
+//! Test that `salsa_event_filter` selects the events passed to `salsa_event`.
+
+mod common;
+use common::{HasLogger, LogDatabase, Logger};
+
+use expect_test::expect;
+use salsa::{Database, EventKindMask, Setter, Storage};
+use test_log::test;
+
+#[salsa::db]
+#[derive(Clone, Default)]
+struct FilteredDatabase {
+    storage: Storage<Self>,
+    logger: Logger,
+}
+
+#[salsa::db]
+impl Database for FilteredDatabase {
+    fn salsa_event(&self, event: &dyn Fn() -> salsa::Event) {
+        let event = event();
+        assert!(self.salsa_event_filter().contains(event.kind.mask()));
+        self.push_log(format!("{:?}", event.kind));
+    }
+
+    fn salsa_event_filter(&self) -> EventKindMask {
+        EventKindMask::WILL_EXECUTE | EventKindMask::DID_VALIDATE_MEMOIZED_VALUE
+    }
+}
+
+impl HasLogger for FilteredDatabase {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn tracked_fn(db: &dyn salsa::Database, input: MyInput) -> u32 {
+    input.field(db) * 2
+}
+
+#[test]
+fn filtered_events() {
+    let mut db = FilteredDatabase::default();
+    let input = MyInput::new(&db, 22);
+    let other = MyInput::new(&db, 23);
+    assert_eq!(tracked_fn(&db, input), 44);
+    assert_eq!(tracked_fn(&db, other), 46);
+    db.assert_logs(expect![[r#"
+        [
+            "WillExecute { database_key: tracked_fn(Id(0)) }",
+            "WillExecute { database_key: tracked_fn(Id(1)) }",
+        ]"#]]);
+
+    // No `DidSetCancellationFlag` or `WillCheckCancellation` events.
+    input.set_field(&mut db).to(24);
+    assert_eq!(tracked_fn(&db, input), 48);
+    assert_eq!(tracked_fn(&db, other), 46);
+    db.assert_logs(expect![[r#"
+        [
+            "WillExecute { database_key: tracked_fn(Id(0)) }",
+            "DidValidateMemoizedValue { database_key: tracked_fn(Id(1)) }",
+        ]"#]]);
+}
+
+#[test]
+fn masks() {
+    let mask = EventKindMask::WILL_EXECUTE | EventKindMask::WILL_BLOCK_ON;
+    assert!(mask.contains(EventKindMask::WILL_EXECUTE));
+    assert!(!mask.contains(EventKindMask::WILL_CHECK_CANCELLATION));
+    assert!(EventKindMask::ALL.contains(mask));
+    assert!(!EventKindMask::NONE.contains(mask));
+    assert_eq!(
+        mask.difference(EventKindMask::WILL_BLOCK_ON),
+        EventKindMask::WILL_EXECUTE
+    );
+    assert_eq!(
+        salsa::EventKind::WillCheckCancellation.mask(),
+        EventKindMask::WILL_CHECK_CANCELLATION
+    );
+}
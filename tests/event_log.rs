@@ -0,0 +1,163 @@
+//! Test recording events with `EventRecorder` and reading the log back.
+#![cfg(feature = "event-log")]
+
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+use expect_test::expect;
+use salsa::{
+    Database, EventKindMask, EventRecorder, RecordedEvent, RecordedEventKind, Setter, Storage,
+};
+use test_log::test;
+
+/// A buffer shared between the recorder and the test.
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[salsa::db]
+#[derive(Clone)]
+struct RecordingDatabase {
+    storage: Storage<Self>,
+    recorder: Arc<EventRecorder>,
+}
+
+impl RecordingDatabase {
+    fn new(buffer: SharedBuffer) -> Self {
+        Self {
+            storage: Storage::default(),
+            recorder: Arc::new(EventRecorder::new(buffer)),
+        }
+    }
+}
+
+#[salsa::db]
+impl Database for RecordingDatabase {
+    fn salsa_event(&self, event: &dyn Fn() -> salsa::Event) {
+        self.recorder.record(self, &event()).unwrap();
+    }
+
+    fn salsa_event_filter(&self) -> EventKindMask {
+        EventKindMask::ALL.difference(EventKindMask::WILL_CHECK_CANCELLATION)
+    }
+}
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn double(db: &dyn Database, input: MyInput) -> u32 {
+    input.field(db) * 2
+}
+
+#[salsa::tracked]
+fn is_positive(db: &dyn Database, input: MyInput) -> bool {
+    double(db, input) > 0
+}
+
+#[test]
+fn record_and_read_back() {
+    let buffer = SharedBuffer::default();
+    let mut db = RecordingDatabase::new(buffer.clone());
+
+    let input = MyInput::new(&db, 1);
+    assert!(is_positive(&db, input));
+    input.set_field(&mut db).to(2);
+    assert!(is_positive(&db, input));
+
+    let log = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+    expect![[r#"
+        {"seq":0,"revision":1,"thread":"ThreadId(_)","kind":"WillExecute","key":{"ingredient":"is_positive","ingredient_index":2,"id":0}}
+        {"seq":1,"revision":1,"thread":"ThreadId(_)","kind":"WillExecute","key":{"ingredient":"double","ingredient_index":3,"id":0}}
+        {"seq":2,"revision":1,"thread":"ThreadId(_)","kind":"DidStoreMemo","key":{"ingredient":"double","ingredient_index":3,"id":0},"changed_at":1,"durability":0}
+        {"seq":3,"revision":1,"thread":"ThreadId(_)","kind":"DidStoreMemo","key":{"ingredient":"is_positive","ingredient_index":2,"id":0},"changed_at":1,"durability":0}
+        {"seq":4,"revision":1,"thread":"ThreadId(_)","kind":"DidSetCancellationFlag"}
+        {"seq":5,"revision":2,"thread":"ThreadId(_)","kind":"DidStartNewRevision"}
+        {"seq":6,"revision":2,"thread":"ThreadId(_)","kind":"DidSetInputField","key":{"ingredient":"field","ingredient_index":1,"id":0},"durability":0}
+        {"seq":7,"revision":2,"thread":"ThreadId(_)","kind":"WillExecute","key":{"ingredient":"double","ingredient_index":3,"id":0}}
+        {"seq":8,"revision":2,"thread":"ThreadId(_)","kind":"DidStoreMemo","key":{"ingredient":"double","ingredient_index":3,"id":0},"changed_at":2,"durability":0}
+        {"seq":9,"revision":2,"thread":"ThreadId(_)","kind":"WillExecute","key":{"ingredient":"is_positive","ingredient_index":2,"id":0}}
+        {"seq":10,"revision":2,"thread":"ThreadId(_)","kind":"DidBackdateValue","key":{"ingredient":"is_positive","ingredient_index":2,"id":0},"changed_at":1}
+        {"seq":11,"revision":2,"thread":"ThreadId(_)","kind":"DidStoreMemo","key":{"ingredient":"is_positive","ingredient_index":2,"id":0},"changed_at":1,"durability":0}
+    "#]]
+    .assert_eq(&mask_thread_ids(&log));
+
+    // Every line parses back into the event it was written from.
+    let events = RecordedEvent::read_log(log.as_bytes()).unwrap();
+    assert_eq!(events.len(), 12);
+    for (event, line) in events.iter().zip(log.lines()) {
+        assert_eq!(event.to_json(), line);
+    }
+
+    // The queries executed in R2.
+    let executed: Vec<String> = events
+        .iter()
+        .filter(|e| e.revision == 2 && e.kind == RecordedEventKind::WillExecute)
+        .map(|e| e.key.as_ref().unwrap().to_string())
+        .collect();
+    assert_eq!(executed, ["double(0)", "is_positive(0)"]);
+}
+
+#[test]
+fn parse_errors() {
+    let error = RecordedEvent::read_log("\n{\"seq\":0}\n".as_bytes()).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "line 2: missing field `revision` at line 1 column 9"
+    );
+
+    let error = RecordedEvent::parse("{\"seq\":\"zero\"}").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "invalid type: string \"zero\", expected u64 at line 1 column 13"
+    );
+
+    let error = RecordedEvent::parse("{\"seq\":0").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "EOF while parsing an object at line 1 column 8"
+    );
+}
+
+#[test]
+fn escaped_strings_round_trip() {
+    let event = RecordedEvent {
+        seq: 0,
+        revision: 1,
+        thread: "thread \"main\"\n\\".to_string(),
+        kind: RecordedEventKind::WillCheckCancellation,
+        key: None,
+        other_key: None,
+        other_thread: None,
+        changed_at: None,
+        durability: None,
+    };
+    let json = event.to_json();
+    assert_eq!(
+        json,
+        r#"{"seq":0,"revision":1,"thread":"thread \"main\"\n\\","kind":"WillCheckCancellation"}"#
+    );
+    assert_eq!(RecordedEvent::parse(&json).unwrap(), event);
+}
+
+/// Thread ids depend on the order in which tests run.
+fn mask_thread_ids(log: &str) -> String {
+    log.lines()
+        .map(|line| {
+            let start = line.find("ThreadId(").unwrap() + "ThreadId(".len();
+            let end = start + line[start..].find(')').unwrap();
+            format!("{}_{}\n", &line[..start], &line[end..])
+        })
+        .collect()
+}
@@ -0,0 +1,123 @@
+//! Test frozen databases: no cancellation checks, no writes.
+
+mod common;
+use common::{HasLogger, LogDatabase, Logger};
+
+use expect_test::expect;
+use salsa::{Database, Setter, Storage};
+use test_log::test;
+
+#[salsa::db]
+#[derive(Clone, Default)]
+struct EventKindLoggerDatabase {
+    storage: Storage<Self>,
+    logger: Logger,
+}
+
+#[salsa::db]
+impl Database for EventKindLoggerDatabase {
+    fn salsa_event(&self, event: &dyn Fn() -> salsa::Event) {
+        self.push_log(format!("{:?}", event().kind));
+    }
+}
+
+impl HasLogger for EventKindLoggerDatabase {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn tracked_fn(db: &dyn salsa::Database, input: MyInput) -> u32 {
+    input.field(db) * 2
+}
+
+#[test]
+fn no_cancellation_checks() {
+    let mut db = EventKindLoggerDatabase::default();
+    let input = MyInput::new(&db, 22);
+    assert_eq!(tracked_fn(&db, input), 44);
+    db.assert_logs(expect![[r#"
+        [
+            "WillCheckCancellation",
+            "WillExecute { database_key: tracked_fn(Id(0)) }",
+            "DidStoreMemo { database_key: tracked_fn(Id(0)), changed_at: R1, durability: Durability(0) }",
+        ]"#]]);
+
+    db.freeze();
+    assert!(db.is_frozen());
+
+    // Queries can still be executed in a frozen database.
+    let other = MyInput::new(&db, 23);
+    assert_eq!(tracked_fn(&db, input), 44);
+    assert_eq!(tracked_fn(&db, other), 46);
+    db.assert_logs(expect![[r#"
+        [
+            "WillExecute { database_key: tracked_fn(Id(1)) }",
+            "DidStoreMemo { database_key: tracked_fn(Id(1)), changed_at: R1, durability: Durability(0) }",
+        ]"#]]);
+}
+
+#[test]
+fn memos_from_earlier_revisions_are_verified() {
+    let mut db = common::LoggerDatabase::default();
+    let input = MyInput::new(&db, 22);
+    assert_eq!(tracked_fn(&db, input), 44);
+
+    input.set_field(&mut db).to(23);
+    db.freeze();
+    assert_eq!(tracked_fn(&db, input), 46);
+}
+
+#[test]
+fn memos_are_validated_once() {
+    let mut db = EventKindLoggerDatabase::default();
+    let input = MyInput::new(&db, 22);
+    let other = MyInput::new(&db, 0);
+    assert_eq!(tracked_fn(&db, input), 44);
+
+    other.set_field(&mut db).to(1);
+    db.freeze();
+    db.assert_logs(expect![[r#"
+        [
+            "WillCheckCancellation",
+            "WillExecute { database_key: tracked_fn(Id(0)) }",
+            "DidStoreMemo { database_key: tracked_fn(Id(0)), changed_at: R1, durability: Durability(0) }",
+            "DidSetCancellationFlag",
+            "DidStartNewRevision { revision: R2 }",
+            "DidSetInputField { key: DependencyIndex(IngredientIndex(1), Some(Id(1))), durability: Durability(0) }",
+        ]"#]]);
+
+    // The first access validates the memo from the earlier revision...
+    assert_eq!(tracked_fn(&db, input), 44);
+    db.assert_logs(expect![[r#"
+        [
+            "DidValidateMemoizedValue { database_key: tracked_fn(Id(0)) }",
+        ]"#]]);
+
+    // ...and later ones return it without any verification.
+    assert_eq!(tracked_fn(&db, input), 44);
+    assert_eq!(tracked_fn(&db, input), 44);
+    db.assert_logs(expect!["[]"]);
+}
+
+#[test]
+#[should_panic(expected = "cannot modify a frozen database")]
+fn set_after_freeze() {
+    let mut db = salsa::DatabaseImpl::new();
+    let input = MyInput::new(&db, 22);
+    db.freeze();
+    input.set_field(&mut db).to(23);
+}
+
+#[test]
+fn clones_are_frozen() {
+    let mut db = salsa::DatabaseImpl::new();
+    db.freeze();
+    assert!(db.clone().is_frozen());
+}
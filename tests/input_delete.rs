@@ -0,0 +1,210 @@
+//! Test deleting inputs:
+//!
+//! * memoized values keyed on a deleted input are dropped,
+//! * queries that read from a deleted input are re-executed,
+//! * the ids of deleted inputs are re-used, from the next revision on.
+
+mod common;
+use common::LogDatabase;
+
+use expect_test::expect;
+use salsa::{Database, Durability};
+use test_log::test;
+
+#[salsa::input]
+struct File {
+    text: u32,
+}
+
+#[salsa::input]
+struct Workspace {
+    #[return_ref]
+    files: Vec<File>,
+}
+
+thread_local! {
+    static DROPPED: std::cell::RefCell<Vec<u32>> = const { std::cell::RefCell::new(vec![]) };
+}
+
+fn dropped() -> Vec<u32> {
+    DROPPED.with(|d| d.borrow().clone())
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Bomb {
+    identity: u32,
+}
+
+impl Drop for Bomb {
+    fn drop(&mut self) {
+        DROPPED.with(|d| d.borrow_mut().push(self.identity));
+    }
+}
+
+#[salsa::tracked(return_ref)]
+fn parse(db: &dyn LogDatabase, file: File) -> Bomb {
+    db.push_log(format!("parse({:?})", file.text(db)));
+    Bomb {
+        identity: file.text(db),
+    }
+}
+
+#[salsa::tracked]
+fn text_len(db: &dyn LogDatabase, file: File) -> u32 {
+    db.push_log(format!("text_len({:?})", file.text(db)));
+    file.text(db)
+}
+
+#[salsa::tracked]
+fn total(db: &dyn LogDatabase, workspace: Workspace) -> u32 {
+    db.push_log("total".to_string());
+    workspace.files(db).iter().map(|&f| text_len(db, f)).sum()
+}
+
+#[test]
+fn delete_drops_memos() {
+    let mut db = common::LoggerDatabase::default();
+
+    let a = File::new(&db, 1);
+    let b = File::new(&db, 2);
+    assert_eq!(parse(&db, a).identity, 1);
+    assert_eq!(parse(&db, b).identity, 2);
+    db.assert_logs(expect![[r#"
+        [
+            "parse(1)",
+            "parse(2)",
+        ]"#]]);
+    assert_eq!(dropped(), [] as [u32; 0]);
+
+    a.delete(&mut db);
+    assert_eq!(dropped(), [1]);
+
+    // The memo for `b` is unaffected.
+    assert_eq!(parse(&db, b).identity, 2);
+    db.assert_logs(expect!["[]"]);
+}
+
+#[test]
+fn delete_invalidates_readers() {
+    let mut db = common::LoggerDatabase::default();
+
+    let a = File::new(&db, 1);
+    let b = File::new(&db, 2);
+    let workspace = Workspace::new(&db, vec![a, b]);
+    assert_eq!(total(&db, workspace), 3);
+    db.assert_logs(expect![[r#"
+        [
+            "total",
+            "text_len(1)",
+            "text_len(2)",
+        ]"#]]);
+
+    // Deleting `b` invalidates `total`, even though the workspace did not change.
+    // Here, the new input `c` re-uses the id of `b`, so `total` now reads `c`.
+    b.delete(&mut db);
+    db.synthetic_write(Durability::LOW);
+    let c = File::new(&db, 3);
+    assert_eq!(b, c);
+    assert_eq!(total(&db, workspace), 4);
+    db.assert_logs(expect![[r#"
+        [
+            "total",
+            "text_len(3)",
+        ]"#]]);
+}
+
+#[test]
+fn delete_recycles_id() {
+    let mut db = common::LoggerDatabase::default();
+
+    let a = File::new(&db, 1);
+    assert_eq!(text_len(&db, a), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "text_len(1)",
+        ]"#]]);
+
+    a.delete(&mut db);
+
+    // Stale copies of `a` may still be used in the revision that deleted it,
+    // so its id is not re-used yet.
+    let b = File::new(&db, 2);
+    assert_ne!(a, b);
+
+    // In the next revision, the new input re-uses the id of the deleted one, but none of its memos.
+    db.synthetic_write(Durability::LOW);
+    let b = File::new(&db, 2);
+    assert_eq!(a, b);
+    assert_eq!(text_len(&db, b), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "text_len(2)",
+        ]"#]]);
+
+    // Ids are also recycled when creating inputs in batches.
+    b.delete(&mut db);
+    db.synthetic_write(Durability::LOW);
+    let batch = File::new_batch(&db, [File::builder(3), File::builder(4)]);
+    assert_eq!(batch[0], b);
+    assert_ne!(batch[1], b);
+}
+
+#[test]
+#[should_panic(expected = "access to the fields of a deleted input")]
+fn read_after_delete() {
+    let mut db = common::LoggerDatabase::default();
+
+    let a = File::new(&db, 1);
+    a.delete(&mut db);
+    a.text(&db);
+}
+
+#[salsa::tracked(return_ref)]
+fn name(_db: &dyn LogDatabase, file: File) -> String {
+    format!("{:?}", salsa::plumbing::AsId::as_id(&file))
+}
+
+#[test]
+fn references_into_deleted_input_survive_reuse() {
+    let mut db = common::LoggerDatabase::default();
+
+    let a = File::new(&db, 1);
+    a.delete(&mut db);
+    db.synthetic_write(Durability::LOW);
+
+    // `name` does not read the fields of `a`, so it can be called on the stale id;
+    // re-using the id must not drop the memo that `stale` points into.
+    let stale = name(&db, a);
+    let b = File::new(&db, 2);
+    assert_eq!(a, b);
+    assert_eq!(stale, &format!("{:?}", salsa::plumbing::AsId::as_id(&a)));
+    assert_eq!(name(&db, b), stale);
+}
+
+#[test]
+fn reuse_while_stale_ids_are_used() {
+    let mut db = common::LoggerDatabase::default();
+
+    let stale: Vec<File> = (0..100).map(|text| File::new(&db, text)).collect();
+    for &file in &stale {
+        file.delete(&mut db);
+    }
+    db.synthetic_write(Durability::LOW);
+
+    // Another thread calls `name` on the stale ids, which accesses their slots,
+    // while this thread re-uses the slots for new inputs.
+    std::thread::scope(|scope| {
+        let reader = db.clone();
+        let stale = &stale;
+        scope.spawn(move || {
+            for &file in stale {
+                name(&reader, file);
+            }
+        });
+
+        let files = File::new_batch(&db, (100..200).map(File::builder));
+        for (file, text) in files.into_iter().zip(100..) {
+            assert_eq!(file.text(&db), text);
+        }
+    });
+}
@@ -0,0 +1,101 @@
+//! Test that inputs created with `new_batch` behave like inputs created one by one.
+
+use salsa::{Durability, Setter};
+use test_log::test;
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+
+    #[default]
+    optional_field: bool,
+}
+
+#[salsa::tracked]
+fn tracked_fn(db: &dyn salsa::Database, input: MyInput) -> u32 {
+    input.field(db) * 2
+}
+
+#[test]
+fn new_batch() {
+    let mut db = salsa::DatabaseImpl::new();
+
+    // Create enough inputs to span several table pages.
+    let inputs = MyInput::new_batch(&db, (0..2500).map(MyInput::builder));
+    assert_eq!(inputs.len(), 2500);
+
+    for (i, input) in (0..).zip(&inputs) {
+        assert_eq!(input.field(&db), i);
+        assert!(!input.optional_field(&db));
+        assert_eq!(tracked_fn(&db, *input), i * 2);
+    }
+
+    // Inputs created afterwards get distinct ids.
+    let single = MyInput::new(&db, 22);
+    assert!(!inputs.contains(&single));
+
+    inputs[1000].set_field(&mut db).to(44);
+    assert_eq!(tracked_fn(&db, inputs[1000]), 88);
+    assert_eq!(tracked_fn(&db, inputs[1001]), 2002);
+    assert_eq!(tracked_fn(&db, single), 44);
+}
+
+#[test]
+fn new_batch_with_builder_options() {
+    let db = salsa::DatabaseImpl::new();
+
+    let inputs = MyInput::new_batch(
+        &db,
+        [
+            MyInput::builder(1),
+            MyInput::builder(2).optional_field(true),
+            MyInput::builder(3).durability(Durability::HIGH),
+        ],
+    );
+
+    assert_eq!(
+        inputs
+            .iter()
+            .map(|input| (input.field(&db), input.optional_field(&db)))
+            .collect::<Vec<_>>(),
+        [(1, false), (2, true), (3, false)]
+    );
+}
+
+#[test]
+fn new_batch_empty() {
+    let db = salsa::DatabaseImpl::new();
+
+    let inputs = MyInput::new_batch(&db, []);
+    assert!(inputs.is_empty());
+}
+
+#[test]
+fn new_batch_creating_inputs_in_builders() {
+    let db = salsa::DatabaseImpl::new();
+
+    // The builders are produced lazily, while the batch is being allocated.
+    let mut created = vec![];
+    let inputs = MyInput::new_batch(
+        &db,
+        (0..3).map(|i| {
+            created.push(MyInput::new(&db, i + 10));
+            MyInput::builder(i)
+        }),
+    );
+
+    assert_eq!(
+        inputs
+            .iter()
+            .map(|input| input.field(&db))
+            .collect::<Vec<_>>(),
+        [0, 1, 2]
+    );
+    assert_eq!(
+        created
+            .iter()
+            .map(|input| input.field(&db))
+            .collect::<Vec<_>>(),
+        [10, 11, 12]
+    );
+}
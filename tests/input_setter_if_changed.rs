@@ -0,0 +1,87 @@
+//! Test that `to_if_changed` does not start a new revision
+//! (and hence does not re-validate dependents) when the value is unchanged.
+
+mod common;
+use common::LogDatabase;
+
+use expect_test::expect;
+use salsa::plumbing::ZalsaDatabase;
+use salsa::{Durability, Setter};
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn tracked_fn(db: &dyn LogDatabase, input: MyInput) -> u32 {
+    db.push_log(format!("tracked_fn({:?})", input.field(db)));
+    input.field(db) * 2
+}
+
+#[test]
+fn unchanged_value_keeps_revision() {
+    let mut db = common::LoggerDatabase::default();
+
+    let input = MyInput::new(&db, 22);
+    assert_eq!(tracked_fn(&db, input), 44);
+    db.assert_logs(expect![[r#"
+        [
+            "tracked_fn(22)",
+        ]"#]]);
+
+    let revision = db.zalsa().current_revision();
+    assert_eq!(input.set_field(&mut db).to_if_changed(22), None);
+    assert_eq!(db.zalsa().current_revision(), revision);
+
+    assert_eq!(tracked_fn(&db, input), 44);
+    db.assert_logs(expect!["[]"]);
+}
+
+#[test]
+fn changed_value_starts_revision() {
+    let mut db = common::LoggerDatabase::default();
+
+    let input = MyInput::new(&db, 22);
+    assert_eq!(tracked_fn(&db, input), 44);
+    db.assert_logs(expect![[r#"
+        [
+            "tracked_fn(22)",
+        ]"#]]);
+
+    let revision = db.zalsa().current_revision();
+    assert_eq!(input.set_field(&mut db).to_if_changed(23), Some(22));
+    assert_ne!(db.zalsa().current_revision(), revision);
+
+    assert_eq!(tracked_fn(&db, input), 46);
+    db.assert_logs(expect![[r#"
+        [
+            "tracked_fn(23)",
+        ]"#]]);
+}
+
+#[test]
+fn changed_durability_starts_revision() {
+    let mut db = common::LoggerDatabase::default();
+
+    let input = MyInput::new(&db, 22);
+
+    let revision = db.zalsa().current_revision();
+    assert_eq!(
+        input
+            .set_field(&mut db)
+            .with_durability(Durability::LOW)
+            .to_if_changed(22),
+        None
+    );
+    assert_eq!(db.zalsa().current_revision(), revision);
+
+    assert_eq!(
+        input
+            .set_field(&mut db)
+            .with_durability(Durability::HIGH)
+            .to_if_changed(22),
+        Some(22)
+    );
+    assert_ne!(db.zalsa().current_revision(), revision);
+}
@@ -0,0 +1,198 @@
+//! Test that reads of individual elements of `TrackedVec` and `TrackedMap`
+//! input fields are tracked individually.
+
+mod common;
+use common::LogDatabase;
+
+use expect_test::expect;
+use salsa::{Database, Durability, Setter, TrackedMap, TrackedVec};
+use test_log::test;
+
+#[salsa::input]
+struct File {
+    #[return_ref]
+    lines: TrackedVec<String>,
+}
+
+#[salsa::input]
+struct Config {
+    #[return_ref]
+    settings: TrackedMap<String, u32>,
+}
+
+#[salsa::tracked]
+fn first_line_len(db: &dyn LogDatabase, file: File) -> usize {
+    db.push_log("first_line_len".to_string());
+    file.lines(db).get(db, 0).map_or(0, |line| line.len())
+}
+
+#[salsa::tracked]
+fn line_count(db: &dyn LogDatabase, file: File) -> usize {
+    db.push_log("line_count".to_string());
+    file.lines(db).len()
+}
+
+#[salsa::tracked]
+fn total_len(db: &dyn LogDatabase, file: File) -> usize {
+    db.push_log("total_len".to_string());
+    file.lines(db).iter(db).map(|line| line.len()).sum()
+}
+
+#[salsa::tracked]
+fn setting(db: &dyn LogDatabase, config: Config, key: Key) -> Option<u32> {
+    db.push_log(format!("setting({:?})", key.name(db)));
+    config.settings(db).get(db, key.name(db).as_str()).copied()
+}
+
+#[salsa::input]
+struct Key {
+    name: String,
+}
+
+#[test]
+fn tracked_vec() {
+    let mut db = common::LoggerDatabase::default();
+
+    let lines = TrackedVec::new(&db, ["a".to_string(), "bb".to_string()]);
+    let file = File::new(&db, lines);
+
+    assert_eq!(first_line_len(&db, file), 1);
+    assert_eq!(line_count(&db, file), 2);
+    assert_eq!(total_len(&db, file), 3);
+    db.assert_logs(expect![[r#"
+        [
+            "first_line_len",
+            "line_count",
+            "total_len",
+        ]"#]]);
+
+    // Changing the second line only invalidates readers of the second line.
+    let old = file.lines(&db).clone().set(&mut db, 1, "ccc".to_string());
+    assert_eq!(old, "bb");
+    assert_eq!(first_line_len(&db, file), 1);
+    assert_eq!(line_count(&db, file), 2);
+    assert_eq!(total_len(&db, file), 4);
+    db.assert_logs(expect![[r#"
+        [
+            "total_len",
+        ]"#]]);
+
+    // Changing the structure invalidates all readers of the field.
+    let lines = TrackedVec::new(&db, ["a".to_string()]);
+    file.set_lines(&mut db).to(lines);
+    assert_eq!(first_line_len(&db, file), 1);
+    assert_eq!(line_count(&db, file), 1);
+    assert_eq!(total_len(&db, file), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "first_line_len",
+            "line_count",
+            "total_len",
+        ]"#]]);
+}
+
+#[test]
+fn released_slots_are_reused() {
+    let mut db = common::LoggerDatabase::default();
+
+    // Without an attached database, collections print the ids of their slots.
+    let lines = TrackedVec::new(&db, ["a".to_string(), "bb".to_string()]);
+    let slots = format!("{lines:?}");
+    let file = File::new(&db, lines);
+    assert_eq!(total_len(&db, file), 3);
+
+    // Replacing the field drops the last clone of the collection, releasing its slots.
+    let lines = TrackedVec::new(&db, ["c".to_string()]);
+    assert_ne!(format!("{lines:?}"), slots);
+    file.set_lines(&mut db).to(lines);
+    assert_eq!(total_len(&db, file), 1);
+
+    // The released slots are re-used from the next revision on.
+    db.synthetic_write(Durability::LOW);
+    let lines = TrackedVec::new(&db, ["dd".to_string(), "eee".to_string()]);
+    assert_eq!(format!("{lines:?}"), slots);
+    file.set_lines(&mut db).to(lines);
+    assert_eq!(total_len(&db, file), 5);
+    db.assert_logs(expect![[r#"
+        [
+            "total_len",
+            "total_len",
+            "total_len",
+        ]"#]]);
+}
+
+#[test]
+fn tracked_map() {
+    let mut db = common::LoggerDatabase::default();
+
+    let settings = TrackedMap::new(&db, [("a".to_string(), 1), ("b".to_string(), 2)]);
+    let config = Config::new(&db, settings);
+    let a = Key::new(&db, "a".to_string());
+    let b = Key::new(&db, "b".to_string());
+    let c = Key::new(&db, "c".to_string());
+
+    assert_eq!(setting(&db, config, a), Some(1));
+    assert_eq!(setting(&db, config, b), Some(2));
+    assert_eq!(setting(&db, config, c), None);
+    db.assert_logs(expect![[r#"
+        [
+            "setting(\"a\")",
+            "setting(\"b\")",
+            "setting(\"c\")",
+        ]"#]]);
+
+    let old = config.settings(&db).clone().set(&mut db, "b", 3);
+    assert_eq!(old, 2);
+    assert_eq!(setting(&db, config, a), Some(1));
+    assert_eq!(setting(&db, config, b), Some(3));
+    assert_eq!(setting(&db, config, c), None);
+    db.assert_logs(expect![[r#"
+        [
+            "setting(\"b\")",
+        ]"#]]);
+}
+
+#[test]
+fn tracked_map_repeated_key() {
+    let mut db = common::LoggerDatabase::default();
+
+    // Each value holds a clone of `live`, until the slot of its element is released.
+    let live = std::sync::Arc::new(());
+    let settings = TrackedMap::new(
+        &db,
+        [
+            ("a", live.clone()),
+            ("b", live.clone()),
+            ("a", live.clone()),
+        ],
+    );
+    assert_eq!(settings.keys().copied().collect::<Vec<_>>(), ["a", "b"]);
+    assert_eq!(std::sync::Arc::strong_count(&live), 3);
+
+    // The value for the repeated key was dropped right away, the others once their slots
+    // are released in the next revision.
+    drop(settings);
+    db.synthetic_write(Durability::LOW);
+    assert_eq!(std::sync::Arc::strong_count(&live), 1);
+}
+
+#[test]
+#[should_panic(expected = "cannot set the value of a key that is not in the map")]
+fn tracked_map_set_missing_key() {
+    let mut db = common::LoggerDatabase::default();
+
+    let settings = TrackedMap::new(&db, [("a".to_string(), 1)]);
+    settings.set(&mut db, "b", 2);
+}
+
+#[test]
+fn debug() {
+    let db = common::LoggerDatabase::default();
+
+    let lines = TrackedVec::new(&db, ["a".to_string(), "bb".to_string()]);
+    let settings = TrackedMap::new(&db, [("a".to_string(), 1)]);
+    salsa::Database::attach(&db, |_| {
+        assert_eq!(format!("{lines:?}"), r#"["a", "bb"]"#);
+        assert_eq!(format!("{settings:?}"), r#"{"a": 1}"#);
+    });
+}
@@ -0,0 +1,79 @@
+//! Tests that `update` applies several field changes in a single revision.
+
+use salsa::plumbing::ZalsaDatabase;
+use salsa::{Durability, Setter};
+use test_log::test;
+
+#[salsa::input]
+struct MyInput {
+    field_a: u32,
+    field_b: String,
+
+    #[default]
+    field_c: bool,
+}
+
+#[test]
+fn update_bumps_revision_once() {
+    let mut db = salsa::DatabaseImpl::new();
+    let input = MyInput::new(&db, 22, "hello".to_string());
+
+    assert_eq!(format!("{:?}", db.zalsa().current_revision()), "R1");
+    input
+        .update(&mut db)
+        .field_a(23)
+        .field_b("world".to_string())
+        .field_c(true)
+        .apply();
+    assert_eq!(format!("{:?}", db.zalsa().current_revision()), "R2");
+    assert_eq!(input.field_a(&db), 23);
+    assert_eq!(input.field_b(&db), "world");
+    assert!(input.field_c(&db));
+
+    // Setting the fields one at a time starts one revision per field.
+    input.set_field_a(&mut db).to(24);
+    input.set_field_b(&mut db).to("!".to_string());
+    assert_eq!(format!("{:?}", db.zalsa().current_revision()), "R4");
+}
+
+#[test]
+fn update_without_changes_does_not_bump_revision() {
+    let mut db = salsa::DatabaseImpl::new();
+    let input = MyInput::new(&db, 22, "hello".to_string());
+
+    let revision_before = db.zalsa().current_revision();
+    input.update(&mut db).apply();
+    assert_eq!(db.zalsa().current_revision(), revision_before);
+}
+
+#[test]
+fn update_with_durability() {
+    let mut db = salsa::DatabaseImpl::new();
+    let input = MyInput::new(&db, 22, "hello".to_string());
+
+    input
+        .update(&mut db)
+        .field_a(23)
+        .field_a_durability(Durability::HIGH)
+        .field_b("world".to_string())
+        .apply();
+
+    let last_high_revision = db.zalsa().last_changed_revision(Durability::HIGH);
+
+    // Only `field_a` has high durability, so changing it again should
+    // bump the high durability revision.
+    input.update(&mut db).field_a(24).apply();
+    assert_ne!(
+        db.zalsa().last_changed_revision(Durability::HIGH),
+        last_high_revision
+    );
+
+    let last_high_revision = db.zalsa().last_changed_revision(Durability::HIGH);
+
+    // `field_b` still has low durability.
+    input.update(&mut db).field_b("!".to_string()).apply();
+    assert_eq!(
+        db.zalsa().last_changed_revision(Durability::HIGH),
+        last_high_revision
+    );
+}
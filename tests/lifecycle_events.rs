@@ -0,0 +1,117 @@
+//! Test the events that describe the lifecycle of memos, inputs, and interned values.
+
+mod common;
+use common::{HasLogger, LogDatabase, Logger};
+
+use expect_test::expect;
+use salsa::{Database, EventKindMask, Setter, Storage};
+use test_log::test;
+
+#[salsa::db]
+#[derive(Clone, Default)]
+struct LifecycleDatabase {
+    storage: Storage<Self>,
+    logger: Logger,
+}
+
+#[salsa::db]
+impl Database for LifecycleDatabase {
+    fn salsa_event(&self, event: &dyn Fn() -> salsa::Event) {
+        self.attach(|_| self.push_log(format!("{:?}", event().kind)));
+    }
+
+    fn salsa_event_filter(&self) -> EventKindMask {
+        EventKindMask::DID_STORE_MEMO
+            | EventKindMask::DID_BACKDATE_VALUE
+            | EventKindMask::DID_EVICT_VALUE
+            | EventKindMask::DID_START_NEW_REVISION
+            | EventKindMask::DID_SET_INPUT_FIELD
+            | EventKindMask::DID_INTERN_VALUE
+    }
+}
+
+impl HasLogger for LifecycleDatabase {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::interned]
+struct MyInterned<'db> {
+    field: u32,
+}
+
+#[salsa::tracked(lru = 1)]
+fn is_small(db: &dyn salsa::Database, input: MyInput) -> bool {
+    input.field(db) < 10
+}
+
+#[salsa::tracked]
+fn intern_field(db: &dyn salsa::Database, input: MyInput) -> u32 {
+    MyInterned::new(db, input.field(db)).field(db)
+}
+
+#[test]
+fn store_and_backdate() {
+    let mut db = LifecycleDatabase::default();
+    let input = MyInput::new(&db, 2);
+    assert!(is_small(&db, input));
+    db.assert_logs(expect![[r#"
+        [
+            "DidStoreMemo { database_key: is_small(Id(0)), changed_at: R1, durability: Durability(0) }",
+        ]"#]]);
+
+    input.set_field(&mut db).to(4);
+    assert!(is_small(&db, input));
+    db.assert_logs(expect![[r#"
+        [
+            "DidStartNewRevision { revision: R2 }",
+            "DidSetInputField { key: field(Id(0)), durability: Durability(0) }",
+            "DidBackdateValue { database_key: is_small(Id(0)), changed_at: R1 }",
+            "DidStoreMemo { database_key: is_small(Id(0)), changed_at: R1, durability: Durability(0) }",
+        ]"#]]);
+}
+
+#[test]
+fn update_reports_each_field() {
+    let mut db = LifecycleDatabase::default();
+    let input = MyInput::new(&db, 2);
+    input.update(&mut db).field(3).apply();
+    db.assert_logs(expect![[r#"
+        [
+            "DidStartNewRevision { revision: R2 }",
+            "DidSetInputField { key: field(Id(0)), durability: Durability(0) }",
+        ]"#]]);
+}
+
+#[test]
+fn evict() {
+    let db = LifecycleDatabase::default();
+    let a = MyInput::new(&db, 1);
+    let b = MyInput::new(&db, 2);
+    assert!(is_small(&db, a));
+    assert!(is_small(&db, b));
+    db.assert_logs(expect![[r#"
+        [
+            "DidStoreMemo { database_key: is_small(Id(0)), changed_at: R1, durability: Durability(0) }",
+            "DidStoreMemo { database_key: is_small(Id(1)), changed_at: R1, durability: Durability(0) }",
+            "DidEvictValue { database_key: is_small(Id(0)) }",
+        ]"#]]);
+}
+
+#[test]
+fn intern() {
+    let db = LifecycleDatabase::default();
+    let input = MyInput::new(&db, 22);
+    assert_eq!(intern_field(&db, input), 22);
+    db.assert_logs(expect![[r#"
+        [
+            "DidInternValue { key: MyInterned(Id(400)) }",
+            "DidStoreMemo { database_key: intern_field(Id(0)), changed_at: R1, durability: Durability(0) }",
+        ]"#]]);
+}
@@ -0,0 +1,111 @@
+//! Test that the query stack is captured when a panic unwinds out of a tracked function.
+
+use expect_test::expect;
+use salsa::{Database, QueryStack};
+use test_log::test;
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn outer(db: &dyn Database, input: MyInput) -> u32 {
+    inner(db, input)
+}
+
+#[salsa::tracked]
+fn inner(db: &dyn Database, input: MyInput) -> u32 {
+    if input.field(db) == 0 {
+        panic!("field is zero");
+    }
+    input.field(db)
+}
+
+#[test]
+fn capture_on_panic() {
+    let db = salsa::DatabaseImpl::new();
+    assert_eq!(QueryStack::last_panic(&db), None);
+
+    let input = MyInput::new(&db, 0);
+    let payload = std::panic::catch_unwind(|| outer(&db, input)).unwrap_err();
+    assert_eq!(payload.downcast_ref::<&str>(), Some(&"field is zero"));
+
+    // Frames are rendered with the ingredient names of the attached database.
+    let stack = QueryStack::last_panic(&db).unwrap();
+    assert_eq!(stack.frames().len(), 2);
+    expect!["[DependencyIndex(IngredientIndex(2), Some(Id(0))), DependencyIndex(IngredientIndex(3), Some(Id(0)))]"]
+        .assert_eq(&format!("{stack:?}"));
+    expect!["[outer(Id(0)), inner(Id(0))]"].assert_eq(&db.attach(|_| format!("{stack:?}")));
+    expect![[r#"
+        query stack (most recent call first):
+           0: inner(Id(0))
+           1: outer(Id(0))
+    "#]]
+    .assert_eq(&db.attach(|_| stack.to_string()));
+}
+
+#[salsa::tracked]
+fn catch_then_panic(db: &dyn Database, input: MyInput) -> u32 {
+    let caught = catch_inner(db, input);
+    panic!("caught {caught}");
+}
+
+#[salsa::tracked]
+fn catch_inner(db: &dyn Database, input: MyInput) -> u32 {
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| inner(db, input))).unwrap_or(0)
+}
+
+#[test]
+fn capture_after_caught_panic() {
+    let db = salsa::DatabaseImpl::new();
+    let input = MyInput::new(&db, 0);
+    let payload = std::panic::catch_unwind(|| catch_then_panic(&db, input)).unwrap_err();
+    assert_eq!(
+        payload.downcast_ref::<String>().map(String::as_str),
+        Some("caught 0")
+    );
+
+    // The stack of the panic caught by `catch_inner` is not reported for the later one.
+    let stack = QueryStack::last_panic(&db).unwrap();
+    expect!["[catch_then_panic(Id(0))]"].assert_eq(&db.attach(|_| format!("{stack:?}")));
+}
+
+#[salsa::tracked(recovery_fn = recover_cycle)]
+fn cycle_a(db: &dyn Database, input: MyInput) -> u32 {
+    cycle_b(db, input)
+}
+
+#[salsa::tracked]
+fn cycle_b(db: &dyn Database, input: MyInput) -> u32 {
+    cycle_a(db, input)
+}
+
+fn recover_cycle(_db: &dyn Database, _cycle: &salsa::Cycle, _input: MyInput) -> u32 {
+    0
+}
+
+#[test]
+fn no_capture_on_recovered_cycle() {
+    let db = salsa::DatabaseImpl::new();
+    let input = MyInput::new(&db, 1);
+
+    // `cycle_b` unwinds with the cycle, which `cycle_a` recovers from: no panic reaches the user.
+    assert_eq!(cycle_a(&db, input), 0);
+    assert_eq!(QueryStack::last_panic(&db), None);
+}
+
+#[test]
+fn capture_current_stack() {
+    #[salsa::tracked]
+    fn capture(db: &dyn Database, input: MyInput) -> QueryStack {
+        let _ = input.field(db);
+        QueryStack::capture(db)
+    }
+
+    let db = salsa::DatabaseImpl::new();
+    let input = MyInput::new(&db, 1);
+    assert!(QueryStack::capture(&db).is_empty());
+    let stack = capture(&db, input);
+    expect!["[capture(Id(0))]"].assert_eq(&db.attach(|_| format!("{stack:?}")));
+}
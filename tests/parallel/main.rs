@@ -5,5 +5,15 @@ mod parallel_cycle_all_recover;
 mod parallel_cycle_mid_recover;
 mod parallel_cycle_none_recover;
 mod parallel_cycle_one_recover;
+mod parallel_deterministic;
+mod parallel_executor;
+mod parallel_freeze;
+mod parallel_join;
 mod parallel_map;
+mod parallel_panic_query_stack;
+#[cfg(feature = "rayon")]
+mod parallel_tracked_struct_ids;
+#[cfg(feature = "rayon")]
+mod parallel_verification;
+mod parallel_wait_for_graph;
 mod signal;
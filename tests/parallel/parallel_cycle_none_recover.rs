@@ -63,6 +63,7 @@ fn execute() {
                 ]
             "#]];
             expected.assert_debug_eq(&c.all_participants(&db));
+            assert_eq!(c.render(&db), "a(Id(0)) => b(Id(0)) => a(Id(0))");
         } else {
             panic!("b failed in an unexpected way: {:?}", err_b);
         }
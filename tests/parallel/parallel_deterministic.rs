@@ -0,0 +1,114 @@
+//! Test that a cycle is reported the same way whether it occurs on one thread
+//! or across threads with deterministic execution, and differently without it.
+
+use salsa::{CycleContext, Storage};
+
+use crate::setup::{Knobs, KnobsDatabase};
+
+#[salsa::input]
+struct MyInput {
+    parallel: bool,
+}
+
+#[salsa::tracked(recovery_fn = recover)]
+fn a(db: &dyn KnobsDatabase, input: MyInput) -> String {
+    if input.parallel(db) {
+        // Wait to create the cycle until both threads have entered
+        db.signal(1);
+        db.wait_for(2);
+    }
+    b(db, input)
+}
+
+#[salsa::tracked(recovery_fn = recover)]
+fn b(db: &dyn KnobsDatabase, input: MyInput) -> String {
+    if input.parallel(db) {
+        // Wait to create the cycle until both threads have entered
+        db.wait_for(1);
+        db.signal(2);
+
+        // Wait for thread A to block on this thread
+        db.wait_for(3);
+    }
+    a(db, input)
+}
+
+fn recover(db: &dyn KnobsDatabase, context: &CycleContext, _input: MyInput) -> String {
+    format!(
+        "{:?} recovered (head {:?}): {}",
+        context.key(),
+        context.head(),
+        context.render(db),
+    )
+}
+
+/// Executes `b`, which enters the cycle (and so closes it when `a` calls `b` again),
+/// and then `a`, which reads the memo stored by the recovery, on one thread.
+fn sequential(db: Knobs) -> (String, String) {
+    let input = MyInput::new(&db, false);
+    let b = b(&db, input);
+    (a(&db, input), b)
+}
+
+// Thread A                   Thread B
+// --------                   --------
+// a                          b
+// |                          wait for stage 1 (blocks)
+// signal stage 1             |
+// wait for stage 2 (blocks)  (unblocked)
+// |                          signal stage 2
+// (unblocked)                wait for stage 3 (blocks)
+// b (blocks -> stage 3)      |
+// |                          (unblocked)
+// |                          a (cycle detected, recovers)
+// b recovers
+fn parallel(db: Knobs) -> (String, String) {
+    let input = MyInput::new(&db, true);
+
+    let thread_a = std::thread::spawn({
+        let db = db.clone();
+        db.knobs().signal_on_will_block.store(3);
+        move || a(&db, input)
+    });
+
+    let thread_b = std::thread::spawn({
+        let db = db.clone();
+        move || b(&db, input)
+    });
+
+    (thread_a.join().unwrap(), thread_b.join().unwrap())
+}
+
+#[test]
+fn head_and_threads_depend_on_interleaving_by_default() {
+    let (a, b) = sequential(Knobs::default());
+    assert_eq!(
+        a,
+        "a(Id(0)) recovered (head b(Id(0))): a(Id(0)) -> b(Id(0)) -> a(Id(0))"
+    );
+    assert_eq!(
+        b,
+        "b(Id(0)) recovered (head b(Id(0))): a(Id(0)) -> b(Id(0)) -> a(Id(0))"
+    );
+
+    let (a, b) = parallel(Knobs::default());
+    assert_eq!(
+        a,
+        "a(Id(0)) recovered (head a(Id(0))): a(Id(0)) => b(Id(0)) => a(Id(0))"
+    );
+    assert_eq!(
+        b,
+        "b(Id(0)) recovered (head a(Id(0))): a(Id(0)) => b(Id(0)) => a(Id(0))"
+    );
+}
+
+#[test]
+fn deterministic_execution_reports_cycles_alike() {
+    let storage = || Storage::default().with_deterministic_execution();
+    let expected = (
+        "a(Id(0)) recovered (head a(Id(0))): a(Id(0)) -> b(Id(0)) -> a(Id(0))".to_string(),
+        "b(Id(0)) recovered (head a(Id(0))): a(Id(0)) -> b(Id(0)) -> a(Id(0))".to_string(),
+    );
+    assert_eq!(sequential(Knobs::with_storage(storage())), expected);
+    assert_eq!(parallel(Knobs::with_storage(storage())), expected);
+}
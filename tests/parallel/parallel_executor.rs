@@ -0,0 +1,195 @@
+//! Tests for running parallel operations with a custom `salsa::Executor`.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use salsa::{Accumulator, ScopeOp, Setter, Storage, Task};
+
+/// An executor that runs each task on a thread of its own, and counts the tasks.
+#[derive(Clone, Default)]
+struct ThreadExecutor {
+    tasks: Arc<AtomicUsize>,
+}
+
+impl ThreadExecutor {
+    fn spawn_scoped<'scope>(
+        &self,
+        scope: &'scope std::thread::Scope<'scope, '_>,
+        task: impl FnOnce() + Send + 'scope,
+    ) {
+        self.tasks.fetch_add(1, Ordering::SeqCst);
+        std::thread::Builder::new()
+            .name("thread-executor".to_string())
+            .spawn_scoped(scope, task)
+            .unwrap();
+    }
+}
+
+impl salsa::Executor for ThreadExecutor {
+    fn join(&self, a: Task<'_>, b: Task<'_>) {
+        std::thread::scope(|scope| {
+            self.spawn_scoped(scope, a);
+            self.spawn_scoped(scope, b);
+        })
+    }
+
+    fn for_each(&self, len: usize, op: &(dyn Fn(usize) + Sync)) {
+        std::thread::scope(|scope| {
+            for index in 0..len {
+                self.spawn_scoped(scope, move || op(index));
+            }
+        })
+    }
+
+    fn scope<'scope>(&self, op: ScopeOp<'_, 'scope>) {
+        std::thread::scope(|scope| op(&|task| self.spawn_scoped(scope, task)))
+    }
+
+    fn spawn(&self, task: Task<'static>) {
+        self.tasks.fetch_add(1, Ordering::SeqCst);
+        std::thread::Builder::new()
+            .name("thread-executor".to_string())
+            .spawn(task)
+            .unwrap();
+    }
+}
+
+#[salsa::db]
+#[derive(Clone)]
+struct ExecutorDatabase {
+    storage: Storage<Self>,
+    executor: ThreadExecutor,
+}
+
+impl Default for ExecutorDatabase {
+    fn default() -> Self {
+        let executor = ThreadExecutor::default();
+        Self {
+            storage: Storage::default().with_executor(executor.clone()),
+            executor,
+        }
+    }
+}
+
+#[salsa::db]
+impl salsa::Database for ExecutorDatabase {
+    fn salsa_event(&self, _event: &dyn Fn() -> salsa::Event) {}
+}
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::input]
+struct Inputs {
+    #[return_ref]
+    inputs: Vec<MyInput>,
+}
+
+fn field_on_thread(db: &dyn salsa::Database, input: MyInput) -> (u32, String) {
+    let thread = std::thread::current()
+        .name()
+        .unwrap_or_default()
+        .to_string();
+    (input.field(db), thread)
+}
+
+#[salsa::tracked]
+fn joined(db: &dyn salsa::Database, a: MyInput, b: MyInput) -> Vec<(u32, String)> {
+    let (a, b) = salsa::join(db, |db| field_on_thread(db, a), |db| field_on_thread(db, b));
+    vec![a, b]
+}
+
+#[salsa::tracked]
+fn mapped(db: &dyn salsa::Database, inputs: Inputs) -> Vec<(u32, String)> {
+    salsa::par_map(db, inputs.inputs(db).clone(), field_on_thread)
+}
+
+#[salsa::tracked]
+fn scoped(db: &dyn salsa::Database, inputs: Inputs) -> Vec<(u32, String)> {
+    let results = Mutex::new(vec![]);
+    salsa::scope(db, |scope| {
+        for &input in inputs.inputs(db) {
+            let results = &results;
+            scope.spawn(move |db| results.lock().push(field_on_thread(db, input)));
+        }
+    });
+    let mut results = results.into_inner();
+    results.sort();
+    results
+}
+
+#[salsa::accumulator]
+struct Log(u32);
+
+#[salsa::tracked]
+fn log_fields(db: &dyn salsa::Database, inputs: Inputs) {
+    let len = inputs.inputs(db).len();
+    let completed = AtomicUsize::new(0);
+    let inputs = inputs.inputs(db).iter().copied().enumerate();
+    salsa::par_for_each(db, inputs, |db, (index, input)| {
+        // Each task completes after all the tasks that follow it.
+        while completed.load(Ordering::SeqCst) != len - 1 - index {
+            std::thread::yield_now();
+        }
+        Log(input.field(db)).accumulate(db);
+        completed.fetch_add(1, Ordering::SeqCst);
+    });
+}
+
+fn on_executor(values: &[u32]) -> Vec<(u32, String)> {
+    values
+        .iter()
+        .map(|&value| (value, "thread-executor".to_string()))
+        .collect()
+}
+
+#[test]
+fn join() {
+    let mut db = ExecutorDatabase::default();
+    let a = MyInput::new(&db, 1);
+    let b = MyInput::new(&db, 2);
+    assert_eq!(joined(&db, a, b), on_executor(&[1, 2]));
+    assert_eq!(db.executor.tasks.load(Ordering::SeqCst), 2);
+
+    b.set_field(&mut db).to(3);
+    assert_eq!(joined(&db, a, b), on_executor(&[1, 3]));
+}
+
+#[test]
+fn par_map() {
+    let mut db = ExecutorDatabase::default();
+    let input: Vec<_> = (1..=4).map(|i| MyInput::new(&db, i)).collect();
+    let inputs = Inputs::new(&db, input.clone());
+    assert_eq!(mapped(&db, inputs), on_executor(&[1, 2, 3, 4]));
+    assert_eq!(db.executor.tasks.load(Ordering::SeqCst), 4);
+
+    input[3].set_field(&mut db).to(5);
+    assert_eq!(mapped(&db, inputs), on_executor(&[1, 2, 3, 5]));
+}
+
+#[test]
+fn scope() {
+    let mut db = ExecutorDatabase::default();
+    let input: Vec<_> = (1..=4).map(|i| MyInput::new(&db, i)).collect();
+    let inputs = Inputs::new(&db, input.clone());
+    assert_eq!(scoped(&db, inputs), on_executor(&[1, 2, 3, 4]));
+    assert_eq!(db.executor.tasks.load(Ordering::SeqCst), 4);
+
+    input[0].set_field(&mut db).to(5);
+    assert_eq!(scoped(&db, inputs), on_executor(&[2, 3, 4, 5]));
+}
+
+#[test]
+fn accumulated_in_task_order() {
+    let db = ExecutorDatabase::default();
+    let input: Vec<_> = (1..=4).map(|i| MyInput::new(&db, i)).collect();
+    let inputs = Inputs::new(&db, input);
+    let logs: Vec<u32> = log_fields::accumulated::<Log>(&db, inputs)
+        .into_iter()
+        .map(|log| log.0)
+        .collect();
+    assert_eq!(logs, [1, 2, 3, 4]);
+}
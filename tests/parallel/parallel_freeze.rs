@@ -0,0 +1,51 @@
+//! Test freezing the database while another handle waits to start a new revision.
+
+use salsa::{Database, Setter};
+
+use crate::setup::{Knobs, KnobsDatabase};
+
+#[salsa::input]
+struct MyInput {
+    field: i32,
+}
+
+// Freeze signalling test
+//
+// Thread A                   Thread B
+// --------                   --------
+// set input, triggers        wait for stage 1
+// cancellation, which
+// sends stage 1, waits
+// for B to drop its handle
+//                            freeze, drop handle
+// (unblocked)
+// panics: frozen
+#[test]
+fn freeze_while_cancelling() {
+    let mut db = Knobs::default();
+    let input = MyInput::new(&db, 1);
+
+    let thread_b = std::thread::spawn({
+        let mut db = db.clone();
+        move || {
+            db.wait_for(1);
+            db.freeze();
+        }
+    });
+
+    db.signal_on_did_cancel.store(1);
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        input.set_field(&mut db).to(2);
+    }));
+    thread_b.join().unwrap();
+
+    let payload = result.unwrap_err();
+    assert_eq!(
+        payload.downcast_ref::<&str>(),
+        Some(&"cannot modify a frozen database")
+    );
+
+    // The frozen database still holds the old value.
+    assert!(db.is_frozen());
+    assert_eq!(input.field(&db), 1);
+}
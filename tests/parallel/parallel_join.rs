@@ -0,0 +1,283 @@
+//! Tests for `salsa::join`, `salsa::scope` and `salsa::spawn`.
+
+use std::sync::Arc;
+use std::thread::ThreadId;
+
+use parking_lot::Mutex;
+use salsa::{Cancelled, Setter};
+
+use crate::setup::{Knobs, KnobsDatabase};
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn double(db: &dyn salsa::Database, input: MyInput) -> u32 {
+    input.field(db) * 2
+}
+
+#[salsa::tracked]
+fn triple(db: &dyn salsa::Database, input: MyInput) -> u32 {
+    input.field(db) * 3
+}
+
+#[salsa::tracked]
+fn joined(db: &dyn salsa::Database, input: MyInput) -> (u32, u32, String) {
+    let ((a, thread), b) = salsa::join(
+        db,
+        |db| (double(db, input), current_thread_name()),
+        |db| triple(db, input),
+    );
+    (a, b, thread)
+}
+
+fn current_thread_name() -> String {
+    std::thread::current()
+        .name()
+        .unwrap_or_default()
+        .to_string()
+}
+
+#[test]
+fn join() {
+    let db = salsa::DatabaseImpl::new();
+    let input = MyInput::new(&db, 10);
+    let (a, b, _) = joined(&db, input);
+    assert_eq!((a, b), (20, 30));
+}
+
+#[test]
+fn join_records_task_reads() {
+    let mut db = salsa::DatabaseImpl::new();
+    let input = MyInput::new(&db, 10);
+    let (a, b, _) = joined(&db, input);
+    assert_eq!((a, b), (20, 30));
+
+    input.set_field(&mut db).to(20);
+    let (a, b, _) = joined(&db, input);
+    assert_eq!((a, b), (40, 60));
+}
+
+#[test]
+#[cfg(feature = "rayon")]
+fn join_on_own_thread_pool() {
+    let db = CloneRecordingDatabase {
+        storage: salsa::Storage::default().with_threads(2),
+        ..Default::default()
+    };
+    let input = MyInput::new(&db, 10);
+    let (a, b, thread) = joined(&db, input);
+    assert_eq!((a, b), (20, 30));
+    assert!(thread.starts_with("salsa-worker-"), "{thread}");
+}
+
+#[salsa::tracked]
+fn sum_in_scope(db: &dyn salsa::Database, inputs: Inputs) -> u32 {
+    let results = Mutex::new(vec![]);
+    salsa::scope(db, |scope| {
+        for &input in inputs.inputs(db) {
+            let results = &results;
+            scope.spawn(move |db| results.lock().push(double(db, input)));
+        }
+    });
+    results.into_inner().into_iter().sum()
+}
+
+#[salsa::input]
+struct Inputs {
+    #[return_ref]
+    inputs: Vec<MyInput>,
+}
+
+#[test]
+fn scope() {
+    let db = salsa::DatabaseImpl::new();
+    let inputs: Vec<_> = (1..=10).map(|i| MyInput::new(&db, i)).collect();
+    let inputs = Inputs::new(&db, inputs);
+    assert_eq!(sum_in_scope(&db, inputs), 110);
+}
+
+#[test]
+fn scope_records_task_reads() {
+    let mut db = salsa::DatabaseImpl::new();
+    let inputs: Vec<_> = (1..=10).map(|i| MyInput::new(&db, i)).collect();
+    let input = inputs[4];
+    let inputs = Inputs::new(&db, inputs);
+    assert_eq!(sum_in_scope(&db, inputs), 110);
+
+    input.set_field(&mut db).to(10);
+    assert_eq!(sum_in_scope(&db, inputs), 120);
+}
+
+/// A database that records the threads on which it is cloned.
+#[salsa::db]
+#[derive(Default)]
+struct CloneRecordingDatabase {
+    storage: salsa::Storage<Self>,
+    clones: Arc<Mutex<Vec<ThreadId>>>,
+}
+
+impl Clone for CloneRecordingDatabase {
+    fn clone(&self) -> Self {
+        self.clones.lock().push(std::thread::current().id());
+        Self {
+            storage: self.storage.clone(),
+            clones: self.clones.clone(),
+        }
+    }
+}
+
+#[salsa::db]
+impl salsa::Database for CloneRecordingDatabase {
+    fn salsa_event(&self, _event: &dyn Fn() -> salsa::Event) {}
+}
+
+#[test]
+fn scope_forks_on_owning_thread() {
+    let db = CloneRecordingDatabase::default();
+    let inputs: Vec<_> = (1..=100).map(|i| MyInput::new(&db, i)).collect();
+    let inputs = Inputs::new(&db, inputs);
+    assert_eq!(sum_in_scope(&db, inputs), 10100);
+
+    // `op` may use the database while the tasks run, so their handles are forked on this thread.
+    let clones = db.clones.lock();
+    assert!(!clones.is_empty());
+    assert!(clones
+        .iter()
+        .all(|&thread| thread == std::thread::current().id()));
+}
+
+#[test]
+fn handles_are_reused_across_operations() {
+    let db = CloneRecordingDatabase::default();
+    for i in 1..=10 {
+        let input = MyInput::new(&db, i);
+        assert_eq!(joined(&db, input).0, i * 2);
+    }
+
+    // Each join runs two tasks at most, which take the idle handles of the previous ones.
+    assert!(db.clones.lock().len() <= 2);
+}
+
+#[test]
+fn idle_handles_are_dropped_with_the_database() {
+    let db = CloneRecordingDatabase::default();
+    let input = MyInput::new(&db, 10);
+    joined(&db, input);
+    assert!(!db.clones.lock().is_empty());
+
+    let clones = db.clones.clone();
+    drop(db);
+    assert_eq!(Arc::strong_count(&clones), 1);
+}
+
+#[test]
+fn spawn() {
+    let db = salsa::DatabaseImpl::new();
+    let input = MyInput::new(&db, 10);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let db: &dyn salsa::Database = &db;
+    salsa::spawn(db, move |db| sender.send(double(db, input)).unwrap());
+    assert_eq!(receiver.recv().unwrap(), 20);
+}
+
+#[salsa::tracked]
+fn spawn_waiting(db: &dyn KnobsDatabase, input: MyInput) {
+    salsa::spawn(db, move |db| {
+        db.signal(1);
+        db.wait_for(2);
+        input.field(db);
+    });
+}
+
+// Cancellation signalling test
+//
+// Spawned task               Main thread
+// ------------               -----------
+//                            wait for stage 1
+// signal stage 1             set input, triggers cancellation
+// wait for stage 2           triggering cancellation sends stage 2
+// (unblocked)                waits for the task
+// reads the input, is cancelled
+//                            (unblocked)
+#[test]
+#[cfg(feature = "rayon")]
+fn spawn_is_cancelled() {
+    let mut db = Knobs::default();
+    let input = MyInput::new(&db, 10);
+    spawn_waiting(&db, input);
+
+    db.wait_for(1);
+    db.signal_on_did_cancel.store(2);
+    input.set_field(&mut db).to(20);
+    assert_eq!(input.field(&db), 20);
+}
+
+#[salsa::tracked]
+fn join_panics(db: &dyn salsa::Database, input: MyInput) -> u32 {
+    let (a, b) = salsa::join(
+        db,
+        |db| double(db, input),
+        |_| -> u32 { panic!("task panicked") },
+    );
+    a + b
+}
+
+#[test]
+fn join_propagates_panics() {
+    let db = salsa::DatabaseImpl::new();
+    let input = MyInput::new(&db, 10);
+    let payload = std::panic::catch_unwind(|| join_panics(&db, input)).unwrap_err();
+    assert_eq!(payload.downcast_ref::<&str>(), Some(&"task panicked"));
+}
+
+#[salsa::tracked]
+fn join_waits(db: &dyn KnobsDatabase, input: MyInput) -> u32 {
+    db.signal(1);
+    let (a, b) = salsa::join(
+        db,
+        |db| {
+            db.wait_for(2);
+            input.field(db)
+        },
+        |db| input.field(db),
+    );
+    a + b
+}
+
+// Cancellation signalling test
+//
+// Thread A                   Main thread
+// --------                   -----------
+// join_waits
+// |                          wait for stage 1
+// signal stage 1             set input, triggers cancellation
+// join: wait for stage 2     triggering cancellation sends stage 2
+// (unblocked)
+// task reads the input, is cancelled
+#[test]
+fn join_propagates_cancellation() {
+    let mut db = Knobs::default();
+    let input = MyInput::new(&db, 10);
+
+    let thread_a = std::thread::spawn({
+        let db = db.clone();
+        move || join_waits(&db, input)
+    });
+
+    db.wait_for(1);
+    db.signal_on_did_cancel.store(2);
+    input.set_field(&mut db).to(20);
+
+    let cancelled = thread_a
+        .join()
+        .unwrap_err()
+        .downcast::<Cancelled>()
+        .unwrap();
+    expect_test::expect![[r#"
+        PendingWrite
+    "#]]
+    .assert_debug_eq(&cancelled);
+}
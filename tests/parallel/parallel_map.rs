@@ -1,5 +1,9 @@
 // test for rayon interations.
 
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use salsa::plumbing::{AsId, FromId};
+use salsa::Accumulator;
 use salsa::Cancelled;
 use salsa::Setter;
 
@@ -26,6 +30,138 @@ fn execute() {
     tracked_fn(&db, input);
 }
 
+#[salsa::tracked]
+fn offset_by(db: &dyn salsa::Database, input: ParallelInput, offset: u32) -> Vec<u32> {
+    salsa::par_map_with(db, input.field(db), |_db, field| field + offset)
+}
+
+#[salsa::tracked]
+fn sum_with(db: &dyn salsa::Database, input: ParallelInput) -> u32 {
+    let sum = AtomicU32::new(0);
+    salsa::par_for_each(db, input.field(db), |_db, field| {
+        sum.fetch_add(field, Ordering::Relaxed);
+    });
+    sum.into_inner()
+}
+
+#[salsa::tracked]
+fn multiples_of(db: &dyn salsa::Database, input: ParallelInput, factor: u32) -> Vec<u32> {
+    salsa::par_filter_map(db, input.field(db), |_db, field| {
+        (field % factor == 0).then_some(field)
+    })
+}
+
+#[test]
+fn execute_with_captures() {
+    let db = salsa::DatabaseImpl::new();
+
+    let counts = (1..=10).collect::<Vec<u32>>();
+    let input = ParallelInput::new(&db, counts);
+
+    assert_eq!(offset_by(&db, input, 10), (11..=20).collect::<Vec<u32>>());
+    assert_eq!(sum_with(&db, input), 55);
+    assert_eq!(multiples_of(&db, input, 3), [3, 6, 9]);
+}
+
+#[salsa::input]
+struct Item {
+    value: u32,
+}
+
+#[salsa::input]
+struct Items {
+    #[return_ref]
+    items: Vec<Item>,
+}
+
+#[salsa::tracked]
+fn item_values(db: &dyn salsa::Database, items: Items) -> Vec<u32> {
+    salsa::par_map_with(db, items.items(db).clone(), |db, item| item.value(db))
+}
+
+#[salsa::tracked]
+struct Parity<'db> {
+    is_even: bool,
+}
+
+#[salsa::tracked]
+fn parities(db: &dyn salsa::Database, items: Items) -> Vec<Parity<'_>> {
+    // Tracked structs cannot escape the task's handle, only their ids.
+    let ids: Vec<salsa::Id> = salsa::par_map_with(db, items.items(db).clone(), |db, item| {
+        Parity::new(db, item.value(db) % 2 == 0).as_id()
+    });
+    ids.into_iter().map(Parity::from_id).collect()
+}
+
+#[salsa::tracked]
+fn even_count(db: &dyn salsa::Database, items: Items) -> usize {
+    let parities = parities(db, items);
+    let distinct: std::collections::HashSet<_> = parities.iter().collect();
+    assert_eq!(distinct.len(), parities.len());
+    parities.iter().filter(|parity| parity.is_even(db)).count()
+}
+
+#[salsa::accumulator]
+struct Log(String);
+
+#[salsa::tracked]
+fn log_values(db: &dyn salsa::Database, items: Items) {
+    salsa::par_for_each(db, items.items(db).clone(), |db, item| {
+        Log(format!("value {}", item.value(db))).accumulate(db)
+    })
+}
+
+fn items(db: &dyn salsa::Database) -> (Items, Vec<Item>) {
+    let items: Vec<_> = (1..=6).map(|i| Item::new(db, i)).collect();
+    (Items::new(db, items.clone()), items)
+}
+
+#[test]
+fn records_task_reads() {
+    let mut db = salsa::DatabaseImpl::new();
+    let (items, item) = items(&db);
+    assert_eq!(item_values(&db, items), [1, 2, 3, 4, 5, 6]);
+
+    item[2].set_value(&mut db).to(30);
+    assert_eq!(item_values(&db, items), [1, 2, 30, 4, 5, 6]);
+}
+
+#[test]
+fn tracked_structs_created_by_tasks() {
+    let mut db = salsa::DatabaseImpl::new();
+    let (items, item) = items(&db);
+    assert_eq!(even_count(&db, items), 3);
+
+    item[0].set_value(&mut db).to(2);
+    assert_eq!(even_count(&db, items), 4);
+
+    item[1].set_value(&mut db).to(3);
+    assert_eq!(even_count(&db, items), 3);
+}
+
+#[test]
+fn accumulated_by_tasks() {
+    let mut db = salsa::DatabaseImpl::new();
+    let (items, item) = items(&db);
+    // The values are accumulated in the order of the items, whichever task completes first.
+    let accumulated = |db: &salsa::DatabaseImpl| {
+        log_values::accumulated::<Log>(db, items)
+            .into_iter()
+            .map(|log| log.0)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        accumulated(&db),
+        ["value 1", "value 2", "value 3", "value 4", "value 5", "value 6"]
+    );
+
+    item[5].set_value(&mut db).to(0);
+    assert_eq!(
+        accumulated(&db),
+        ["value 1", "value 2", "value 3", "value 4", "value 5", "value 0"]
+    );
+}
+
 #[salsa::tracked]
 fn a1(db: &dyn KnobsDatabase, input: ParallelInput) -> Vec<u32> {
     db.signal(1);
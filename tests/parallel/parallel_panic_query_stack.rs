@@ -0,0 +1,75 @@
+//! Test that a thread blocked on a query that panics on another thread
+//! is cancelled with the query stack of the panicking thread.
+
+use salsa::{Cancelled, Database};
+
+use crate::setup::{Knobs, KnobsDatabase};
+
+#[salsa::input]
+struct MyInput {
+    field: i32,
+}
+
+#[salsa::tracked]
+fn a1(db: &dyn KnobsDatabase, input: MyInput) -> i32 {
+    db.signal(1);
+    db.wait_for(2);
+    a2(db, input)
+}
+
+#[salsa::tracked]
+fn a2(_db: &dyn KnobsDatabase, _input: MyInput) -> i32 {
+    panic!("a2 panics")
+}
+
+#[salsa::tracked]
+fn b1(db: &dyn KnobsDatabase, input: MyInput) -> i32 {
+    a1(db, input)
+}
+
+// Thread A                   Thread B
+// --------                   --------
+// a1                         wait for stage 1
+// signal stage 1             b1
+// wait for stage 2           a1, blocks on thread A
+// |                          signal stage 2
+// (unblocked)                |
+// a2 panics                  (unblocked)
+//                            cancelled with A's query stack
+#[test]
+fn execute() {
+    let db = Knobs::default();
+
+    let input = MyInput::new(&db, 1);
+
+    let thread_a = std::thread::spawn({
+        let db = db.clone();
+        move || a1(&db, input)
+    });
+
+    let thread_b = std::thread::spawn({
+        let db = db.clone();
+        move || {
+            db.wait_for(1);
+            db.signal_on_will_block.store(2);
+            b1(&db, input)
+        }
+    });
+
+    assert!(thread_a.join().is_err());
+
+    let cancelled = thread_b
+        .join()
+        .unwrap_err()
+        .downcast::<Cancelled>()
+        .unwrap();
+    expect_test::expect![[r#"
+        PropagatedPanic {
+            query_stack: [
+                a1(Id(0)),
+                a2(Id(0)),
+            ],
+        }
+    "#]]
+    .assert_eq(&db.attach(|_| format!("{cancelled:#?}\n")));
+}
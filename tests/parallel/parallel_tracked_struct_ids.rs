@@ -0,0 +1,52 @@
+//! Test that tracked structs created by parallel tasks keep their ids
+//! across executions, whichever task creates its structs first.
+
+use salsa::plumbing::AsId;
+use salsa::{Id, Setter};
+
+use crate::setup::{Knobs, KnobsDatabase};
+
+#[salsa::input]
+struct MyInput {
+    /// The index of the task that creates its struct first.
+    first: usize,
+
+    /// The stage signalled by the first task once it has created its struct.
+    stage: usize,
+}
+
+/// All structs have the same (empty) identity hash.
+#[salsa::tracked]
+struct MyTracked<'db> {
+    task: usize,
+}
+
+#[salsa::tracked]
+fn create_in_tasks(db: &dyn KnobsDatabase, input: MyInput) -> (Id, Id) {
+    let create = |db: &dyn KnobsDatabase, task: usize| {
+        let (first, stage) = (input.first(db), input.stage(db));
+        if task != first {
+            db.wait_for(stage);
+        }
+        let tracked = MyTracked::new(db, task);
+        if task == first {
+            db.signal(stage);
+        }
+        assert_eq!(tracked.task(db), task);
+        tracked.as_id()
+    };
+    salsa::join(db, |db| create(db, 0), |db| create(db, 1))
+}
+
+#[test]
+fn same_ids_in_any_order() {
+    let mut db = Knobs::with_storage(salsa::Storage::default().with_threads(2));
+    let input = MyInput::new(&db, 0, 1);
+    let ids = create_in_tasks(&db, input);
+    assert_ne!(ids.0, ids.1);
+
+    // The tasks create their structs in the opposite order, and still get the same ids.
+    input.set_first(&mut db).to(1);
+    input.set_stage(&mut db).to(2);
+    assert_eq!(create_in_tasks(&db, input), ids);
+}
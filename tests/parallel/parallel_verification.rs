@@ -0,0 +1,229 @@
+//! Tests for verifying the inputs of a memo in parallel
+//! (see `Storage::with_parallel_verification`).
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use salsa::{Database, Durability, ScopeOp, Setter, Storage, Task};
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::input]
+struct Inputs {
+    #[return_ref]
+    inputs: Vec<MyInput>,
+}
+
+#[salsa::tracked]
+fn leaf(db: &dyn salsa::Database, input: MyInput) -> u32 {
+    input.field(db) * 2
+}
+
+#[salsa::tracked]
+fn fan_out(db: &dyn salsa::Database, inputs: Inputs) -> u32 {
+    inputs.inputs(db).iter().map(|&input| leaf(db, input)).sum()
+}
+
+#[salsa::input]
+struct Groups {
+    #[return_ref]
+    groups: Vec<Inputs>,
+}
+
+#[salsa::tracked]
+fn fan_out_groups(db: &dyn salsa::Database, groups: Groups) -> u32 {
+    groups
+        .groups(db)
+        .iter()
+        .map(|&inputs| fan_out(db, inputs))
+        .sum()
+}
+
+/// An executor that runs the tasks on the current thread, one after the other,
+/// without telling salsa.
+struct InlineExecutor;
+
+impl salsa::Executor for InlineExecutor {
+    fn join(&self, a: Task<'_>, b: Task<'_>) {
+        a();
+        b();
+    }
+
+    fn for_each(&self, len: usize, op: &(dyn Fn(usize) + Sync)) {
+        (0..len).for_each(op)
+    }
+
+    fn scope<'scope>(&self, op: ScopeOp<'_, 'scope>) {
+        op(&|task| task())
+    }
+
+    fn spawn(&self, task: Task<'static>) {
+        task()
+    }
+}
+
+/// A database that logs executions and validations, with the thread they happen on,
+/// and counts how often it is cloned.
+#[salsa::db]
+struct VerificationDatabase {
+    storage: Storage<Self>,
+    events: Arc<Mutex<Vec<String>>>,
+    clones: Arc<AtomicUsize>,
+}
+
+impl VerificationDatabase {
+    fn new() -> Self {
+        Self::with_storage(Storage::default().with_threads(2))
+    }
+
+    fn with_storage(storage: Storage<Self>) -> Self {
+        Self {
+            storage: storage.with_parallel_verification(4),
+            events: Default::default(),
+            clones: Default::default(),
+        }
+    }
+
+    fn take_events(&self) -> Vec<String> {
+        let mut events = std::mem::take(&mut *self.events.lock());
+        events.sort();
+        events
+    }
+}
+
+impl Clone for VerificationDatabase {
+    fn clone(&self) -> Self {
+        self.clones.fetch_add(1, Ordering::SeqCst);
+        Self {
+            storage: self.storage.clone(),
+            events: self.events.clone(),
+            clones: self.clones.clone(),
+        }
+    }
+}
+
+#[salsa::db]
+impl salsa::Database for VerificationDatabase {
+    fn salsa_event(&self, event: &dyn Fn() -> salsa::Event) {
+        let event = event();
+        if let salsa::EventKind::WillExecute { .. }
+        | salsa::EventKind::DidValidateMemoizedValue { .. } = event.kind
+        {
+            let on_worker = std::thread::current()
+                .name()
+                .is_some_and(|name| name.starts_with("salsa-worker-"));
+            let thread = if on_worker { "worker" } else { "caller" };
+            let event = self.attach(|_| format!("{thread}: {:?}", event.kind));
+            self.events.lock().push(event);
+        }
+    }
+}
+
+fn setup(db: &VerificationDatabase) -> Inputs {
+    let inputs: Vec<_> = (1..=4).map(|i| MyInput::new(db, i)).collect();
+    let inputs = Inputs::new(db, inputs);
+    assert_eq!(fan_out(db, inputs), 20);
+    db.take_events();
+    inputs
+}
+
+#[test]
+fn inputs_are_verified_on_workers() {
+    let mut db = VerificationDatabase::new();
+    let inputs = setup(&db);
+
+    db.synthetic_write(Durability::LOW);
+    assert_eq!(fan_out(&db, inputs), 20);
+    expect_test::expect![[r#"
+        [
+            "caller: DidValidateMemoizedValue { database_key: fan_out(Id(400)) }",
+            "worker: DidValidateMemoizedValue { database_key: leaf(Id(0)) }",
+            "worker: DidValidateMemoizedValue { database_key: leaf(Id(1)) }",
+            "worker: DidValidateMemoizedValue { database_key: leaf(Id(2)) }",
+            "worker: DidValidateMemoizedValue { database_key: leaf(Id(3)) }",
+        ]
+    "#]]
+    .assert_debug_eq(&db.take_events());
+}
+
+#[test]
+fn changed_inputs_are_executed_by_the_caller() {
+    let mut db = VerificationDatabase::new();
+    let inputs = setup(&db);
+
+    let input = inputs.inputs(&db)[2];
+    input.set_field(&mut db).to(10);
+    assert_eq!(fan_out(&db, inputs), 34);
+    expect_test::expect![[r#"
+        [
+            "caller: WillExecute { database_key: fan_out(Id(400)) }",
+            "caller: WillExecute { database_key: leaf(Id(2)) }",
+            "worker: DidValidateMemoizedValue { database_key: leaf(Id(0)) }",
+            "worker: DidValidateMemoizedValue { database_key: leaf(Id(1)) }",
+            "worker: DidValidateMemoizedValue { database_key: leaf(Id(3)) }",
+        ]
+    "#]]
+    .assert_debug_eq(&db.take_events());
+}
+
+#[test]
+fn fewer_inputs_are_verified_sequentially() {
+    let mut db = VerificationDatabase::new();
+    let inputs = setup(&db);
+    let first_two = inputs.inputs(&db)[..2].to_vec();
+    inputs.set_inputs(&mut db).to(first_two);
+    assert_eq!(fan_out(&db, inputs), 6);
+    db.take_events();
+
+    db.synthetic_write(Durability::LOW);
+    assert_eq!(fan_out(&db, inputs), 6);
+    expect_test::expect![[r#"
+        [
+            "caller: DidValidateMemoizedValue { database_key: fan_out(Id(400)) }",
+            "caller: DidValidateMemoizedValue { database_key: leaf(Id(0)) }",
+            "caller: DidValidateMemoizedValue { database_key: leaf(Id(1)) }",
+        ]
+    "#]]
+    .assert_debug_eq(&db.take_events());
+}
+
+#[test]
+fn handles_are_reused_across_memos() {
+    let mut db =
+        VerificationDatabase::with_storage(Storage::default().with_executor(InlineExecutor));
+    let groups = vec![setup(&db), setup(&db)];
+    let groups = Groups::new(&db, groups);
+    assert_eq!(fan_out_groups(&db, groups), 40);
+
+    // `fan_out_groups` has too few inputs to verify them in parallel, but each `fan_out`
+    // verifies its own inputs in parallel, with the same speculative handle.
+    db.synthetic_write(Durability::LOW);
+    db.clones.store(0, Ordering::SeqCst);
+    assert_eq!(fan_out_groups(&db, groups), 40);
+    assert_eq!(db.clones.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn single_thread_verifies_sequentially() {
+    let mut db = VerificationDatabase::with_storage(Storage::default().with_threads(1));
+    let inputs = setup(&db);
+
+    // Verifying the inputs speculatively would only verify them twice.
+    db.synthetic_write(Durability::LOW);
+    assert_eq!(fan_out(&db, inputs), 20);
+    expect_test::expect![[r#"
+        [
+            "caller: DidValidateMemoizedValue { database_key: fan_out(Id(400)) }",
+            "caller: DidValidateMemoizedValue { database_key: leaf(Id(0)) }",
+            "caller: DidValidateMemoizedValue { database_key: leaf(Id(1)) }",
+            "caller: DidValidateMemoizedValue { database_key: leaf(Id(2)) }",
+            "caller: DidValidateMemoizedValue { database_key: leaf(Id(3)) }",
+        ]
+    "#]]
+    .assert_debug_eq(&db.take_events());
+    assert_eq!(db.clones.load(Ordering::SeqCst), 0);
+}
@@ -0,0 +1,143 @@
+//! Test inspecting the threads that are blocked on other threads.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use salsa::{Database, Storage};
+use test_log::test;
+
+use crate::setup::{Knobs, KnobsDatabase};
+
+#[salsa::input]
+struct MyInput {
+    field: i32,
+}
+
+#[salsa::tracked]
+fn a1(db: &dyn KnobsDatabase, input: MyInput) -> i32 {
+    db.signal(1);
+    db.wait_for(3);
+    input.field(db)
+}
+
+#[salsa::tracked]
+fn b1(db: &dyn KnobsDatabase, input: MyInput) -> i32 {
+    a1(db, input) + 1
+}
+
+// Thread A                   Thread B                   Main thread
+// --------                   --------                   -----------
+// a1                         wait for stage 1
+// signal stage 1             b1
+// wait for stage 3           a1, blocks on thread A
+// |                          signal stage 2             inspect the graph
+// |                          |                          signal stage 3
+// (unblocked)                |
+// returns                    (unblocked)
+//
+// Thread B logs with `dispatch`, if any.
+fn run(
+    db: Knobs,
+    dispatch: Option<tracing::Dispatch>,
+    inspect: impl FnOnce(&Knobs, std::thread::ThreadId, std::thread::ThreadId),
+) {
+    let input = MyInput::new(&db, 22);
+
+    let thread_a = std::thread::spawn({
+        let db = db.clone();
+        move || a1(&db, input)
+    });
+
+    let thread_b = std::thread::spawn({
+        let db = db.clone();
+        move || {
+            db.wait_for(1);
+            db.signal_on_will_block.store(2);
+            match dispatch {
+                Some(dispatch) => tracing::dispatcher::with_default(&dispatch, || b1(&db, input)),
+                None => b1(&db, input),
+            }
+        }
+    });
+
+    db.wait_for(2);
+    inspect(&db, thread_a.thread().id(), thread_b.thread().id());
+    db.signal(3);
+
+    assert_eq!(thread_a.join().unwrap(), 22);
+    assert_eq!(thread_b.join().unwrap(), 23);
+    assert!(db.wait_for_graph().blocked.is_empty());
+}
+
+#[test]
+fn wait_for_graph() {
+    run(Knobs::default(), None, |db, thread_a, thread_b| {
+        let graph = db.wait_for_graph();
+        assert_eq!(graph.blocked.len(), 1);
+
+        let blocked = &graph.blocked[0];
+        assert_eq!(blocked.thread_id, thread_b);
+        assert_eq!(blocked.blocked_on_thread, thread_a);
+        db.attach(|_| {
+            assert_eq!(format!("{:?}", blocked.blocked_on_key), "a1(Id(0))");
+            assert_eq!(format!("{:?}", blocked.stack), "[b1(Id(0))]");
+        });
+    });
+}
+
+#[test]
+fn watchdog() {
+    let db = Knobs::with_storage(Storage::default().with_watchdog(Duration::from_millis(1)));
+    let logs = Logs::default();
+    let dispatch = tracing::Dispatch::new(
+        tracing_subscriber::fmt()
+            .with_writer(logs.clone())
+            .with_ansi(false)
+            .finish(),
+    );
+
+    // Wait for the watchdog of thread B to fire before unblocking it.
+    run(db, Some(dispatch), |_, _, _| {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !logs.contents().contains("longer than expected") {
+            assert!(Instant::now() < deadline, "the watchdog did not fire");
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    });
+
+    let logs = logs.contents();
+    assert_eq!(logs.matches("longer than expected").count(), 1, "{logs}");
+    assert!(logs.contains("WARN"), "{logs}");
+    assert!(logs.contains("wait-for graph:"), "{logs}");
+    assert!(logs.contains("a1(Id(0))"), "{logs}");
+}
+
+/// Log output, captured by a `tracing` subscriber.
+#[derive(Clone, Default)]
+struct Logs(Arc<Mutex<Vec<u8>>>);
+
+impl Logs {
+    fn contents(&self) -> String {
+        String::from_utf8(self.0.lock().clone()).unwrap()
+    }
+}
+
+impl std::io::Write for Logs {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> tracing_subscriber::fmt::MakeWriter<'a> for Logs {
+    type Writer = Logs;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
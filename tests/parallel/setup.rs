@@ -35,6 +35,16 @@ pub(crate) struct Knobs {
     pub(crate) signal_on_did_cancel: AtomicCell<usize>,
 }
 
+impl Knobs {
+    /// Creates knobs that use the given (customized) storage.
+    pub(crate) fn with_storage(storage: salsa::Storage<Self>) -> Self {
+        Self {
+            storage,
+            ..Default::default()
+        }
+    }
+}
+
 impl Clone for Knobs {
     #[track_caller]
     fn clone(&self) -> Self {
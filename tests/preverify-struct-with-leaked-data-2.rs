@@ -63,8 +63,11 @@ fn test_leaked_inputs_ignored() {
         [
             "Event { thread_id: ThreadId(2), kind: WillCheckCancellation }",
             "Event { thread_id: ThreadId(2), kind: WillExecute { database_key: function(Id(0)) } }",
+            "Event { thread_id: ThreadId(2), kind: DidInternValue { key: Configuration(Id(800)) } }",
             "Event { thread_id: ThreadId(2), kind: WillCheckCancellation }",
             "Event { thread_id: ThreadId(2), kind: WillExecute { database_key: counter_field(Id(800)) } }",
+            "Event { thread_id: ThreadId(2), kind: DidStoreMemo { database_key: counter_field(Id(800)), changed_at: R1, durability: Durability(0) } }",
+            "Event { thread_id: ThreadId(2), kind: DidStoreMemo { database_key: function(Id(0)), changed_at: R1, durability: Durability(0) } }",
         ]"#]]);
 
     assert_eq!(result_in_rev_1, (0, 0));
@@ -80,11 +83,17 @@ fn test_leaked_inputs_ignored() {
     db.assert_logs(expect![[r#"
         [
             "Event { thread_id: ThreadId(2), kind: DidSetCancellationFlag }",
+            "Event { thread_id: ThreadId(2), kind: DidStartNewRevision { revision: R2 } }",
+            "Event { thread_id: ThreadId(2), kind: DidSetInputField { key: DependencyIndex(IngredientIndex(2), Some(Id(0))), durability: Durability(0) } }",
             "Event { thread_id: ThreadId(2), kind: WillCheckCancellation }",
             "Event { thread_id: ThreadId(2), kind: WillCheckCancellation }",
             "Event { thread_id: ThreadId(2), kind: WillExecute { database_key: counter_field(Id(800)) } }",
+            "Event { thread_id: ThreadId(2), kind: DidBackdateValue { database_key: counter_field(Id(800)), changed_at: R1 } }",
+            "Event { thread_id: ThreadId(2), kind: DidStoreMemo { database_key: counter_field(Id(800)), changed_at: R1, durability: Durability(0) } }",
             "Event { thread_id: ThreadId(2), kind: WillExecute { database_key: function(Id(0)) } }",
             "Event { thread_id: ThreadId(2), kind: WillCheckCancellation }",
+            "Event { thread_id: ThreadId(2), kind: DidBackdateValue { database_key: function(Id(0)), changed_at: R1 } }",
+            "Event { thread_id: ThreadId(2), kind: DidStoreMemo { database_key: function(Id(0)), changed_at: R1, durability: Durability(0) } }",
         ]"#]]);
 
     // Salsa will re-execute `counter_field` before re-executing
@@ -62,6 +62,8 @@ fn test_leaked_inputs_ignored() {
             "Event { thread_id: ThreadId(2), kind: WillExecute { database_key: function(Id(0)) } }",
             "Event { thread_id: ThreadId(2), kind: WillCheckCancellation }",
             "Event { thread_id: ThreadId(2), kind: WillExecute { database_key: counter_field(Id(400)) } }",
+            "Event { thread_id: ThreadId(2), kind: DidStoreMemo { database_key: counter_field(Id(400)), changed_at: R1, durability: Durability(0) } }",
+            "Event { thread_id: ThreadId(2), kind: DidStoreMemo { database_key: function(Id(0)), changed_at: R1, durability: Durability(0) } }",
         ]"#]]);
 
     assert_eq!(result_in_rev_1, (0, 0));
@@ -77,11 +79,15 @@ fn test_leaked_inputs_ignored() {
     db.assert_logs(expect![[r#"
         [
             "Event { thread_id: ThreadId(2), kind: DidSetCancellationFlag }",
+            "Event { thread_id: ThreadId(2), kind: DidStartNewRevision { revision: R2 } }",
+            "Event { thread_id: ThreadId(2), kind: DidSetInputField { key: DependencyIndex(IngredientIndex(2), Some(Id(0))), durability: Durability(0) } }",
             "Event { thread_id: ThreadId(2), kind: WillCheckCancellation }",
             "Event { thread_id: ThreadId(2), kind: WillCheckCancellation }",
             "Event { thread_id: ThreadId(2), kind: DidValidateMemoizedValue { database_key: counter_field(Id(400)) } }",
             "Event { thread_id: ThreadId(2), kind: WillExecute { database_key: function(Id(0)) } }",
             "Event { thread_id: ThreadId(2), kind: WillCheckCancellation }",
+            "Event { thread_id: ThreadId(2), kind: DidBackdateValue { database_key: function(Id(0)), changed_at: R1 } }",
+            "Event { thread_id: ThreadId(2), kind: DidStoreMemo { database_key: function(Id(0)), changed_at: R1, durability: Durability(0) } }",
         ]"#]]);
 
     // Because salsa does not see any way for the tracked
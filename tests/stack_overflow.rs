@@ -0,0 +1,157 @@
+//! Test the query depth limit and continuing deep recursion on new threads.
+
+use std::panic::AssertUnwindSafe;
+
+use expect_test::expect;
+use salsa::{Database, StackOverflow, Storage};
+use test_log::test;
+
+#[salsa::db]
+#[derive(Clone)]
+struct Db {
+    storage: Storage<Self>,
+}
+
+#[salsa::db]
+impl Database for Db {
+    fn salsa_event(&self, _event: &dyn Fn() -> salsa::Event) {}
+}
+
+#[salsa::interned]
+struct Num<'db> {
+    value: u64,
+}
+
+#[salsa::tracked]
+fn sum_to<'db>(db: &'db dyn Database, n: Num<'db>) -> u64 {
+    match n.value(db) {
+        0 => 0,
+        value => value + sum_to(db, Num::new(db, value - 1)),
+    }
+}
+
+/// The name of the thread on which the innermost query runs.
+#[salsa::tracked]
+fn innermost_thread<'db>(db: &'db dyn Database, n: Num<'db>) -> String {
+    match n.value(db) {
+        0 => std::thread::current()
+            .name()
+            .unwrap_or("unnamed")
+            .to_string(),
+        value => innermost_thread(db, Num::new(db, value - 1)),
+    }
+}
+
+#[test]
+fn unwind_on_max_depth() {
+    let db = Db {
+        storage: Storage::default().with_max_query_depth(10),
+    };
+
+    let n = Num::new(&db, 9);
+    assert_eq!(sum_to(&db, n), 45);
+
+    let n = Num::new(&db, 20);
+    let stack_overflow = StackOverflow::catch(AssertUnwindSafe(|| sum_to(&db, n))).unwrap_err();
+    assert_eq!(stack_overflow.max_depth(), 10);
+    assert_eq!(stack_overflow.query_stack().frames().len(), 11);
+    expect!["query stack exceeded the maximum depth of 10"].assert_eq(&stack_overflow.to_string());
+    expect![[r#"
+        query stack (most recent call first):
+           0: sum_to(Id(14))
+           1: sum_to(Id(13))
+           2: sum_to(Id(12))
+           3: sum_to(Id(11))
+           4: sum_to(Id(10))
+           5: sum_to(Id(f))
+           6: sum_to(Id(e))
+           7: sum_to(Id(d))
+           8: sum_to(Id(c))
+           9: sum_to(Id(b))
+          10: sum_to(Id(a))
+    "#]]
+    .assert_eq(&db.attach(|_| stack_overflow.query_stack().to_string()));
+
+    // The database is still usable.
+    let n = Num::new(&db, 5);
+    assert_eq!(sum_to(&db, n), 15);
+}
+
+#[test]
+fn continue_on_new_thread() {
+    let db = || Db {
+        storage: Storage::default()
+            .with_max_query_depth(100)
+            .with_overflow_threads(16 * 1024 * 1024),
+    };
+
+    let deep = db();
+    let n = Num::new(&deep, 1000);
+    assert_eq!(sum_to(&deep, n), 500500);
+    assert_eq!(innermost_thread(&deep, n), "salsa-stack-overflow");
+
+    let shallow = db();
+    let n = Num::new(&shallow, 50);
+    assert_eq!(sum_to(&shallow, n), 1275);
+    assert_eq!(
+        innermost_thread(&shallow, n),
+        std::thread::current().name().unwrap()
+    );
+}
+
+#[salsa::tracked]
+fn panic_at_zero<'db>(db: &'db dyn Database, n: Num<'db>) -> u64 {
+    match n.value(db) {
+        0 => panic!("reached zero"),
+        value => panic_at_zero(db, Num::new(db, value - 1)),
+    }
+}
+
+#[test]
+fn panic_on_overflow_thread() {
+    let db = Db {
+        storage: Storage::default()
+            .with_max_query_depth(4)
+            .with_overflow_threads(1024 * 1024),
+    };
+
+    // The panic is resumed on the original thread, with the query stack of all threads.
+    let n = Num::new(&db, 9);
+    let payload = std::panic::catch_unwind(AssertUnwindSafe(|| panic_at_zero(&db, n))).unwrap_err();
+    assert_eq!(payload.downcast_ref::<&str>(), Some(&"reached zero"));
+    assert_eq!(
+        salsa::QueryStack::last_panic(&db).unwrap().frames().len(),
+        10
+    );
+
+    // The database is still usable.
+    let n = Num::new(&db, 5);
+    assert_eq!(sum_to(&db, n), 15);
+}
+
+#[salsa::tracked]
+fn ring<'db>(db: &'db dyn Database, n: Num<'db>) -> u64 {
+    ring(db, Num::new(db, (n.value(db) + 1) % 5))
+}
+
+#[test]
+fn cycle_across_overflow_threads() {
+    let db = Db {
+        storage: Storage::default()
+            .with_max_query_depth(2)
+            .with_overflow_threads(1024 * 1024),
+    };
+
+    // The overflow threads act on behalf of the original thread, so the cycle
+    // is detected rather than deadlocking.
+    let n = Num::new(&db, 0);
+    let payload = std::panic::catch_unwind(AssertUnwindSafe(|| ring(&db, n))).unwrap_err();
+    let cycle = payload.downcast::<salsa::Cycle>().unwrap();
+    assert_eq!(cycle.all_participants(&db).len(), 5);
+}
+
+#[test]
+#[should_panic(expected = "overflow threads require a maximum query depth")]
+fn overflow_threads_without_max_depth() {
+    Storage::<Db>::default().with_overflow_threads(1024 * 1024);
+}
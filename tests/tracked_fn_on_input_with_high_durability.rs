@@ -30,8 +30,10 @@ fn execute() {
         [
             "Event { thread_id: ThreadId(2), kind: WillCheckCancellation }",
             "Event { thread_id: ThreadId(2), kind: WillExecute { database_key: tracked_fn(Id(0)) } }",
+            "Event { thread_id: ThreadId(2), kind: DidStoreMemo { database_key: tracked_fn(Id(0)), changed_at: R1, durability: Durability(0) } }",
             "Event { thread_id: ThreadId(2), kind: WillCheckCancellation }",
             "Event { thread_id: ThreadId(2), kind: WillExecute { database_key: tracked_fn(Id(1)) } }",
+            "Event { thread_id: ThreadId(2), kind: DidStoreMemo { database_key: tracked_fn(Id(1)), changed_at: R1, durability: Durability(2) } }",
         ]"#]]);
 
     db.synthetic_write(Durability::LOW);
@@ -47,6 +49,7 @@ fn execute() {
     db.assert_logs(expect![[r#"
         [
             "Event { thread_id: ThreadId(2), kind: DidSetCancellationFlag }",
+            "Event { thread_id: ThreadId(2), kind: DidStartNewRevision { revision: R2 } }",
             "Event { thread_id: ThreadId(2), kind: WillCheckCancellation }",
             "Event { thread_id: ThreadId(2), kind: DidValidateMemoizedValue { database_key: tracked_fn(Id(0)) } }",
             "Event { thread_id: ThreadId(2), kind: WillCheckCancellation }",